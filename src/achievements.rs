@@ -0,0 +1,305 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! Little goals to work towards. There's a few built in, and more can
+//! be added in the config. Once one is unlocked it stays unlocked, the
+//! unlocked ones are saved next to the history.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use chrono::{DateTime, Local, NaiveDate};
+use color_eyre::Result;
+use log::warn;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    history::{Completion, History, Status},
+    meds::MedicationConfig,
+    state_file,
+    stats::{Counts, Day, Streak, Streaks},
+    task::{Schedule, TaskType},
+};
+
+/// What has to happen to unlock an achievement. Tasks are by name, the
+/// same as in `tasks`, and `Take Meds` covers every medication too
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rule {
+    /// On time every time it was due, this many days in a row. Without
+    /// a task, that's every task
+    Streak {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        task: Option<String>,
+        days: u32,
+    },
+    /// Never missed, this many days in a row. Doing it late or skipping
+    /// it is fine. Without a task, that's every task
+    NoneMissed {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        task: Option<String>,
+        days: u32,
+    },
+    /// Done this many times in one day
+    InADay { task: String, count: usize },
+    /// Done this many times altogether. Without a task, that's every
+    /// task
+    Total {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        task: Option<String>,
+        count: usize,
+    },
+}
+
+impl Rule {
+    fn met(&self, history: &History, days: &[Day], streaks: &Streaks, meds: &MedsGroup) -> bool {
+        // Skipping doesn't count as doing it
+        fn done<'a>(
+            history: &'a History,
+            meds: &'a MedsGroup,
+            task: Option<&'a str>,
+        ) -> impl Iterator<Item = &'a Completion> {
+            history.completions().iter().filter(move |c| {
+                c.status != Status::Skipped && task.is_none_or(|task| meds.covers(task, &c.task))
+            })
+        }
+        match self {
+            Rule::Streak { task: None, days } => streaks.overall.best >= *days,
+            Rule::Streak {
+                task: Some(task),
+                days,
+            } if task == TaskType::TakeMeds.name() => meds.streak.best >= *days,
+            Rule::Streak {
+                task: Some(task),
+                days,
+            } => streaks
+                .tasks
+                .get(task)
+                .is_some_and(|streak| streak.best >= *days),
+            Rule::NoneMissed { task, days: needed } => {
+                let streak = Streak::unmissed(days, |day| match task.as_deref() {
+                    None => day.total,
+                    Some(task) if task == TaskType::TakeMeds.name() => meds.counts(day),
+                    Some(task) => day.tasks.get(task).copied().unwrap_or_default(),
+                });
+                streak.best >= *needed
+            }
+            Rule::InADay { task, count } => {
+                let mut by_day: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+                for completion in done(history, meds, Some(task)) {
+                    *by_day.entry(completion.at.date_naive()).or_default() += 1;
+                }
+                by_day.values().any(|done| done >= count)
+            }
+            Rule::Total { task, count } => done(history, meds, task.as_deref()).count() >= *count,
+        }
+    }
+}
+
+/// Medications are tasks of their own, but they count as taking meds
+struct MedsGroup<'a> {
+    names: &'a [String],
+    /// For the Take Meds task and every medication together
+    streak: Streak,
+}
+
+impl MedsGroup<'_> {
+    /// Whether `task` in a rule means `ty`
+    fn covers(&self, task: &str, ty: &TaskType) -> bool {
+        ty.name() == task
+            || (task == TaskType::TakeMeds.name() && self.names.iter().any(|n| n == ty.name()))
+    }
+
+    /// Take Meds and every medication put together
+    fn counts(&self, day: &Day) -> Counts {
+        day.tasks
+            .iter()
+            .filter(|(name, _)| *name == TaskType::TakeMeds.name() || self.names.contains(name))
+            .fold(Counts::default(), |total, (_, counts)| Counts {
+                expected: total.expected + counts.expected,
+                on_time: total.on_time + counts.on_time,
+                done: total.done + counts.done,
+                missed: total.missed + counts.missed,
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AchievementConfig {
+    pub name: String,
+    /// Shown before it's unlocked, so say how to get it
+    pub description: String,
+    pub rule: Rule,
+}
+
+impl AchievementConfig {
+    fn builtin() -> Vec<Self> {
+        let achievement = |name: &str, description: &str, rule| AchievementConfig {
+            name: name.to_string(),
+            description: description.to_string(),
+            rule,
+        };
+        vec![
+            achievement(
+                "Getting Started",
+                "Do a task",
+                Rule::Total {
+                    task: None,
+                    count: 1,
+                },
+            ),
+            achievement(
+                "Like Clockwork",
+                "Take meds on time for 7 days in a row",
+                Rule::Streak {
+                    task: Some("Take Meds".to_string()),
+                    days: 7,
+                },
+            ),
+            achievement(
+                "Hydrated",
+                "Drink 8 times in one day",
+                Rule::InADay {
+                    task: "Drink".to_string(),
+                    count: 8,
+                },
+            ),
+            achievement(
+                "Well Rested",
+                "Don't miss bedtime for a whole week",
+                Rule::NoneMissed {
+                    task: Some("Sleep".to_string()),
+                    days: 7,
+                },
+            ),
+            achievement(
+                "Perfect Week",
+                "Do everything on time for 7 days in a row",
+                Rule::Streak {
+                    task: None,
+                    days: 7,
+                },
+            ),
+        ]
+    }
+}
+
+pub struct Achievements {
+    path: PathBuf,
+    all: Vec<AchievementConfig>,
+    /// Names of the medications
+    meds: Vec<String>,
+    /// When each one was unlocked, by name
+    unlocked: BTreeMap<String, DateTime<Local>>,
+}
+
+impl Achievements {
+    const FILE: &str = "achievements.json";
+
+    pub fn path(data_dir: &std::path::Path) -> PathBuf {
+        data_dir.join(Self::FILE)
+    }
+
+    pub fn load(
+        path: PathBuf,
+        custom: &[AchievementConfig],
+        medications: &[MedicationConfig],
+    ) -> Result<Self> {
+        let unlocked = state_file::load(&path)?.unwrap_or_default();
+        let mut achievements = Achievements {
+            path,
+            all: Vec::new(),
+            meds: Vec::new(),
+            unlocked,
+        };
+        achievements.reconfigure(custom, medications);
+        Ok(achievements)
+    }
+
+    /// Switch to the custom achievements and medications from a new
+    /// config
+    pub fn reconfigure(&mut self, custom: &[AchievementConfig], medications: &[MedicationConfig]) {
+        self.all = AchievementConfig::builtin();
+        self.all.extend(custom.iter().cloned());
+        self.meds = medications.iter().map(|med| med.name.clone()).collect();
+    }
+
+    /// Every achievement, and when it was unlocked if it has been
+    pub fn iter(&self) -> impl Iterator<Item = (&AchievementConfig, Option<DateTime<Local>>)> {
+        self.all
+            .iter()
+            .map(|achievement| (achievement, self.unlocked.get(&achievement.name).copied()))
+    }
+
+    /// Unlock everything that's been earned, returning the ones that
+    /// weren't unlocked before
+    pub fn check(
+        &mut self,
+        history: &History,
+        schedules: &[(TaskType, Schedule)],
+        now: DateTime<Local>,
+    ) -> Vec<AchievementConfig> {
+        let days = Day::since_start(history, schedules, now);
+        let streaks = Streaks::new(&days);
+        let mut meds = MedsGroup {
+            names: &self.meds,
+            streak: Streak::default(),
+        };
+        meds.streak = Streak::new(&days, |day| meds.counts(day));
+        let new: Vec<_> = self
+            .all
+            .iter()
+            .filter(|achievement| !self.unlocked.contains_key(&achievement.name))
+            .filter(|achievement| achievement.rule.met(history, &days, &streaks, &meds))
+            .cloned()
+            .collect();
+        if !new.is_empty() {
+            for achievement in &new {
+                self.unlocked.insert(achievement.name.clone(), now);
+            }
+            if let Err(e) = self.save() {
+                warn!("Couldn't save to {}: {e}", self.path.display());
+            }
+        }
+        new
+    }
+
+    fn save(&self) -> Result<()> {
+        state_file::save(&self.path, &self.unlocked)
+    }
+
+    /// Unlocked ones first, then the rest with how to get them
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "Achievements ({}/{})",
+            self.iter().filter(|(_, at)| at.is_some()).count(),
+            self.all.len()
+        )];
+        let (unlocked, locked): (Vec<_>, Vec<_>) = self.iter().partition(|(_, at)| at.is_some());
+        for (achievement, at) in unlocked {
+            let at = at.expect("Only unlocked ones").format("%b %-d %Y");
+            lines.push(format!("  * {} (unlocked {at})", achievement.name));
+        }
+        for (achievement, _) in locked {
+            lines.push(format!(
+                "    {}: {}",
+                achievement.name, achievement.description
+            ));
+        }
+        lines
+    }
+}
@@ -0,0 +1,52 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::time::Duration;
+
+use crate::task::TaskType;
+
+/// The longest a task can be snoozed for from outside, anything more
+/// is surely a mistake (and a big enough number doesn't fit in a date)
+pub const MAX_SNOOZE_SECONDS: u64 = 366 * 24 * 60 * 60;
+
+/// The most that can be logged in one go, the same six digits that
+/// can be typed in
+pub const MAX_AMOUNT: u32 = 999_999;
+
+/// Something the user wants to happen, whether it came from a key
+/// press or from outside (the HTTP API, etc.)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Do a task, logging an amount for tasks that keep track of one
+    /// (their usual amount if none is given). Tasks with a guided timer
+    /// start it instead, and are done once it's over
+    Complete(TaskType, Option<u32>),
+    /// Snooze a task, for the configured snooze duration if none is
+    /// given
+    Snooze(TaskType, Option<Duration>),
+    /// Move on to the next time a task is due without doing it
+    Skip(TaskType),
+    /// Add a full refill to a medication's pill count, by name
+    Refill(String),
+    OpenSettings,
+    ToggleHelp,
+    ToggleUpcoming,
+    ToggleDoNotDisturb,
+    ToggleStats,
+    Quit,
+}
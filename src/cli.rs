@@ -0,0 +1,155 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::path::PathBuf;
+
+use chrono::Local;
+use color_eyre::{eyre::bail, Result};
+use directories::ProjectDirs;
+
+use crate::{
+    config::{schema, Config},
+    history::History,
+    stats::Period,
+};
+
+const USAGE: &str = "\
+Usage: tuigotchi-health [COMMAND]
+
+With no command, starts the lil guy.
+
+Commands:
+  --check-config [FILE]  Check a config file for problems (defaults to yours)
+  --migrate-config       Upgrade your config file to the current version,
+                         keeping a backup of the old one
+  --json-schema [FILE]   Write a JSON Schema for the config file, for
+                         editor completion (prints it if there's no FILE)
+  --stats [--json]       Show how often each task gets done on time
+  --help                 Show this message";
+
+/// Things that can be done from the command line instead of running
+/// the TUI
+pub enum Command {
+    Run,
+    Help,
+    CheckConfig(Option<PathBuf>),
+    MigrateConfig,
+    JsonSchema(Option<PathBuf>),
+    Stats { json: bool },
+}
+
+impl Command {
+    pub fn from_args() -> Result<Self> {
+        let mut args = std::env::args().skip(1);
+        let command = match args.next().as_deref() {
+            None => Command::Run,
+            Some("--help" | "-h") => Command::Help,
+            Some("--check-config") => Command::CheckConfig(args.next().map(PathBuf::from)),
+            Some("--migrate-config") => Command::MigrateConfig,
+            Some("--json-schema") => Command::JsonSchema(args.next().map(PathBuf::from)),
+            Some("--stats") => match args.next().as_deref() {
+                None => Command::Stats { json: false },
+                Some("--json") => Command::Stats { json: true },
+                Some(other) => {
+                    eprintln!("Unexpected argument: {other}\n\n{USAGE}");
+                    bail!("Unexpected argument: {other}");
+                }
+            },
+            Some(other) => {
+                eprintln!("Unknown argument: {other}\n\n{USAGE}");
+                bail!("Unknown argument: {other}");
+            }
+        };
+        if let Some(extra) = args.next() {
+            eprintln!("Unexpected argument: {extra}\n\n{USAGE}");
+            bail!("Unexpected argument: {extra}");
+        }
+        Ok(command)
+    }
+
+    /// Run a command line command, this shouldn't be called for
+    /// [`Command::Run`]
+    pub fn run(self, dirs: &ProjectDirs) -> Result<()> {
+        let config_dir = dirs.config_dir();
+        match self {
+            Command::Run => unreachable!("The TUI isn't a command line command"),
+            Command::Help => println!("{USAGE}"),
+            Command::CheckConfig(path) => {
+                let path = path.unwrap_or_else(|| Config::config_path(config_dir));
+                if let Err(e) = Config::from_file(&path) {
+                    eprintln!("There's a problem with {}:\n{e}", path.display());
+                    bail!("Invalid config");
+                }
+                println!("{} looks good!", path.display());
+            }
+            Command::MigrateConfig => {
+                let path = Config::config_path(config_dir);
+                // There's no log yet, so say what went wrong here
+                match Config::migrate_file(&path) {
+                    Ok(Some((version, backup))) => println!(
+                        "Upgraded {} from version {version}, the old one is at {}",
+                        path.display(),
+                        backup.display()
+                    ),
+                    Ok(None) => println!("{} is already up to date", path.display()),
+                    Err(e) => {
+                        eprintln!("Couldn't upgrade {}:\n{e}", path.display());
+                        bail!("Couldn't upgrade the config");
+                    }
+                }
+            }
+            Command::JsonSchema(None) => println!("{}", schema()),
+            Command::JsonSchema(Some(path)) => std::fs::write(path, schema())?,
+            Command::Stats { json } => {
+                let history_path = History::path(dirs.data_dir());
+                let history = match History::load(history_path.clone()) {
+                    Ok(history) => history,
+                    Err(e) => {
+                        eprintln!("Couldn't read {}:\n{e}", history_path.display());
+                        bail!("Couldn't read the history");
+                    }
+                };
+                // The schedules say what should have been done
+                let config = match Config::load_config(config_dir) {
+                    Ok(config) => config,
+                    Err(e) => {
+                        let path = Config::config_path(config_dir);
+                        eprintln!("There's a problem with {}:\n{e}", path.display());
+                        bail!("Invalid config");
+                    }
+                };
+                let schedules: Vec<_> = config
+                    .tasks_with_meds()
+                    .iter()
+                    .map(|task| (task.ty().clone(), task.schedule()))
+                    .collect();
+                let periods = Period::all(&history, &schedules, Local::now());
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&periods)?);
+                } else {
+                    let lines: Vec<_> = periods
+                        .iter()
+                        .map(|period| period.lines().join("\n"))
+                        .collect();
+                    println!("{}", lines.join("\n\n"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -16,13 +16,38 @@ along with Tuigotchi Health. If not, see
 <https://www.gnu.org/licenses/>.
  */
 
-use std::{fs::File, path::Path, time::Duration};
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
 
 use color_eyre::Result;
 use crossterm::style::Color;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::task::Task;
+use crate::{
+    achievements::AchievementConfig,
+    hooks::HooksConfig,
+    http_api::HttpApiConfig,
+    keybinds::KeysConfig,
+    meds::MedicationConfig,
+    mood::MoodConfig,
+    notifier::NotifierConfig,
+    task::Task,
+    unlockables::{Accessory, Theme},
+};
+
+mod edit;
+mod migrate;
+mod schema;
+mod validate;
+
+pub use migrate::CURRENT_VERSION;
+pub use schema::schema;
+pub(crate) use schema::HumanDuration;
 
 #[derive(Serialize, Deserialize)]
 #[serde(remote = "Color")]
@@ -48,43 +73,122 @@ enum ColorDef {
     AnsiValue(u8),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Anything left out gets filled in from the default config when
+/// loading, so nothing is required in the schema either
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[schemars(default)]
 pub struct Config {
+    /// Which version of the config format this is, old configs get
+    /// upgraded when they're loaded
+    pub version: u64,
+    /// Which lil guy to look after
     pub character: CharacterChoice,
+    /// What to call them, instead of the character's usual name
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub character_name: Option<String>,
+    /// Something for them to wear, once it's unlocked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accessory: Option<Accessory>,
+    /// Colours the lil guy in stripes instead of `colour`, once it's
+    /// unlocked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<Theme>,
+    /// How the mood is worked out, `Gentle` never gets sad
+    pub mood: MoodConfig,
+    /// How long tasks can be overdue before they start bothering you more
     #[serde(with = "humantime_serde")]
+    #[schemars(with = "HumanDuration")]
     pub task_timeout: Duration,
+    /// How long tasks can be overdue before the lil guy is at their saddest
     #[serde(with = "humantime_serde")]
+    #[schemars(with = "HumanDuration")]
     pub task_timeout_max: Duration,
+    /// Shortest time between idle animations
     #[serde(with = "humantime_serde")]
+    #[schemars(with = "HumanDuration")]
     pub idle_animation_time_min: Duration,
+    /// Longest time between idle animations
     #[serde(with = "humantime_serde")]
+    #[schemars(with = "HumanDuration")]
     pub idle_animation_time_max: Duration,
+    /// How long the animation plays after finishing a task
     #[serde(with = "humantime_serde")]
+    #[schemars(with = "HumanDuration")]
     pub task_animation_duration: Duration,
+    /// Colour of the lil guy
     #[serde(with = "ColorDef")]
+    #[schemars(with = "ColorDef")]
     pub colour: Color,
+    /// Colour of the text
     #[serde(with = "ColorDef")]
+    #[schemars(with = "ColorDef")]
     pub text_colour: Color,
+    /// Colour of the task list
     #[serde(with = "ColorDef")]
+    #[schemars(with = "ColorDef")]
     pub task_colour: Color,
+    /// Where notifications about tasks go
+    pub notifiers: Vec<NotifierConfig>,
+    /// Commands to run when things happen
+    pub hooks: HooksConfig,
+    /// Turns on the HTTP API on localhost
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_api: Option<HttpApiConfig>,
+    /// Publish a D-Bus service on the session bus (Linux/BSD only)
+    pub dbus_service: bool,
+    /// How long snoozing a task puts it off for
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "HumanDuration")]
+    pub snooze_duration: Duration,
+    /// Which keys do what
+    pub keys: KeysConfig,
+    /// Your own achievements, on top of the built in ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub achievements: Vec<AchievementConfig>,
+    /// The things to look after
     pub tasks: Vec<Task>,
+    /// Medications to take, each one is a task of its own
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub medications: Vec<MedicationConfig>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum CharacterChoice {
     #[serde(rename = "Debug Guy")]
     DebugGuy,
     Kitty,
     Puppy,
+    Lizard,
+    #[serde(rename = "Autism Creature")]
+    AutismCreature,
 }
 
 impl CharacterChoice {
+    pub const ALL: [CharacterChoice; 5] = [
+        CharacterChoice::DebugGuy,
+        CharacterChoice::Kitty,
+        CharacterChoice::Puppy,
+        CharacterChoice::Lizard,
+        CharacterChoice::AutismCreature,
+    ];
+
+    /// The name of the character, the same as it's written in the config
+    pub fn name(&self) -> &'static str {
+        match self {
+            CharacterChoice::DebugGuy => "Debug Guy",
+            CharacterChoice::Kitty => "Kitty",
+            CharacterChoice::Puppy => "Puppy",
+            CharacterChoice::Lizard => "Lizard",
+            CharacterChoice::AutismCreature => "Autism Creature",
+        }
+    }
     pub fn character_name(&self) -> &'static str {
         match self {
             CharacterChoice::DebugGuy => "Debug Guy (very cool)",
             CharacterChoice::Kitty => "Kitted Catte",
             CharacterChoice::Puppy => "Oggywoggy",
+            CharacterChoice::Lizard => "Lizzy",
+            CharacterChoice::AutismCreature => "Autism Creature",
         }
     }
     pub fn animation_file(&self) -> &'static str {
@@ -92,24 +196,104 @@ impl CharacterChoice {
             CharacterChoice::DebugGuy => include_str!("animations/debug_guy.txt"),
             CharacterChoice::Kitty => include_str!("animations/kitty.txt"),
             CharacterChoice::Puppy => include_str!("animations/puppy.txt"),
+            CharacterChoice::Lizard => include_str!("animations/lizard.txt"),
+            CharacterChoice::AutismCreature => include_str!("animations/autism_creature.txt"),
         }
     }
 }
 
 impl Config {
-    pub fn load_config(config_path: impl AsRef<Path>) -> Result<Self> {
-        let mut path = config_path.as_ref().to_path_buf();
-        std::fs::create_dir_all(&path)?;
-        path.push("config.yaml");
+    pub fn config_path(config_dir: impl AsRef<Path>) -> PathBuf {
+        config_dir.as_ref().join("config.yaml")
+    }
+
+    /// Write the JSON schema next to the config at `path`, so it
+    /// always matches the config it was last written with
+    fn write_schema(path: &Path) -> Result<()> {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        std::fs::write(dir.join(schema::SCHEMA_FILE), schema())?;
+        Ok(())
+    }
+
+    pub fn load_config(config_dir: impl AsRef<Path>) -> Result<Self> {
+        std::fs::create_dir_all(&config_dir)?;
+        let path = Self::config_path(config_dir);
         Ok(if path.exists() {
-            serde_yaml::from_str(&std::fs::read_to_string(&path)?)?
+            Self::from_file(&path)?
         } else {
+            // Editors with a YAML language server pick the schema up
+            // from the comment at the top
             let config = Self::default();
-            serde_yaml::to_writer(File::create(&path)?, &config)?;
+            Self::write_schema(&path)?;
+            // As it is, so the commented out examples are there too
+            let mut file = File::create(&path)?;
+            file.write_all(schema::modeline().as_bytes())?;
+            file.write_all(include_str!("default_config.yaml").as_bytes())?;
             config
         })
     }
 
+    /// Load and check a config, see [`validate::parse`]
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(validate::parse(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Upgrade an old config file to the current version, keeping a
+    /// backup of the old one. Returns the old version and where the
+    /// backup is, or `None` if it was already up to date.
+    // Comments in the old file don't survive this, hence the backup
+    pub fn migrate_file(path: impl AsRef<Path>) -> Result<Option<(u64, PathBuf)>> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let value: serde_yaml::Value = serde_yaml::from_str(&text)?;
+        let version = match value.get("version") {
+            Some(v) => v.as_u64(),
+            None => Some(1),
+        };
+        if version == Some(CURRENT_VERSION) {
+            return Ok(None);
+        }
+        // Loading it says what's wrong if the version isn't a whole
+        // number, or is too new
+        let config = Self::from_file(path)?;
+        let version = version.expect("Loading the config checks the version");
+        let backup = path.with_extension(format!("yaml.v{version}.bak"));
+        std::fs::copy(path, &backup)?;
+        let mut file = File::create(path)?;
+        // Keep editors pointed at the schema
+        if let Some(modeline) = text
+            .lines()
+            .find(|line| line.starts_with("# yaml-language-server:"))
+        {
+            writeln!(file, "{modeline}")?;
+        }
+        serde_yaml::to_writer(file, &config)?;
+        Self::write_schema(path)?;
+        Ok(Some((version, backup)))
+    }
+
+    /// Write this config over the one at `path`, keeping its comments
+    /// where possible, see [`edit::update_text`]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let text = edit::update_text(&std::fs::read_to_string(path).unwrap_or_default(), self)?;
+        // Make sure it'll load again before replacing anything
+        validate::parse(&text)?;
+        let temp = path.with_extension("yaml.tmp");
+        std::fs::write(&temp, text)?;
+        std::fs::rename(temp, path)?;
+        Self::write_schema(path)
+    }
+
+    /// The tasks, then one for each medication
+    pub fn tasks_with_meds(&self) -> Vec<Task> {
+        self.tasks
+            .iter()
+            .cloned()
+            .chain(self.medications.iter().map(MedicationConfig::task))
+            .collect()
+    }
+
     pub fn character_name(&self) -> &'_ str {
         self.character_name
             .as_deref()
@@ -122,3 +306,116 @@ impl Default for Config {
         serde_yaml::from_str(include_str!("default_config.yaml")).unwrap()
     }
 }
+
+/// Keeps an eye on the config file, so changes can be applied without
+/// restarting
+// This just checks the modification time every so often, editors
+// like to replace the file instead of writing to it, which makes
+// actually watching it more trouble than it's worth
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    next_check: Instant,
+}
+
+impl ConfigWatcher {
+    const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(path: PathBuf) -> Self {
+        ConfigWatcher {
+            modified: Self::modified(&path),
+            path,
+            next_check: Instant::now() + Self::CHECK_INTERVAL,
+        }
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    pub fn next_check(&self) -> Instant {
+        self.next_check
+    }
+
+    /// The file was changed by us and has already been applied, so
+    /// don't load it again
+    pub fn mark_seen(&mut self) {
+        self.modified = Self::modified(&self.path);
+    }
+
+    /// Returns the newly loaded config if the file has changed since
+    /// last time
+    pub fn poll(&mut self) -> Option<Result<Config>> {
+        let now = Instant::now();
+        if now < self.next_check {
+            return None;
+        }
+        self.next_check = now + Self::CHECK_INTERVAL;
+        let modified = Self::modified(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        Some(Config::from_file(&self.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_configs_have_the_examples() {
+        let dir = std::env::temp_dir().join(format!("tuigotchi-new-config-{}", std::process::id()));
+        let config = Config::load_config(&dir);
+        let text = std::fs::read_to_string(Config::config_path(&dir));
+        std::fs::remove_dir_all(&dir).unwrap();
+        config.unwrap();
+        let text = text.unwrap();
+        assert!(text.starts_with(&schema::modeline()));
+        assert!(text.contains("# medications:"));
+        validate::parse(&text).unwrap();
+    }
+
+    #[test]
+    fn migrating_and_saving_rewrite_the_schema() {
+        let dir = std::env::temp_dir().join(format!("tuigotchi-schema-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = Config::config_path(&dir);
+        let schema_path = dir.join(schema::SCHEMA_FILE);
+        std::fs::write(&path, include_str!("../tests/golden/config_v12.yaml")).unwrap();
+        std::fs::write(&schema_path, "{}").unwrap();
+        let migrated = Config::migrate_file(&path).map(|_| std::fs::read_to_string(&schema_path));
+        std::fs::write(&schema_path, "{}").unwrap();
+        let saved = Config::default()
+            .save(&path)
+            .map(|_| std::fs::read_to_string(&schema_path));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(migrated.unwrap().unwrap(), schema());
+        assert_eq!(saved.unwrap().unwrap(), schema());
+    }
+
+    #[test]
+    fn migrating_checks_the_version() {
+        let v3 = include_str!("../tests/golden/config_v3.yaml");
+        for version in ["\"3\"", "3.5"] {
+            let path = std::env::temp_dir().join(format!(
+                "tuigotchi-migrate-{}-{}.yaml",
+                std::process::id(),
+                version.replace('"', "")
+            ));
+            let text = v3.replace("version: 3", &format!("version: {version}"));
+            assert_ne!(text, v3, "config_v3.yaml doesn't have `version: 3`");
+            std::fs::write(&path, &text).unwrap();
+            let result = Config::migrate_file(&path);
+            let after = std::fs::read_to_string(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            let error = result.expect_err(&format!("version {version} was migrated"));
+            assert!(
+                error.to_string().contains("whole number"),
+                "version {version} gave the wrong error: {error}"
+            );
+            assert_eq!(after, text, "version {version} was written over");
+        }
+    }
+}
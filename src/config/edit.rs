@@ -0,0 +1,111 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! Writing changes back into an existing config file.
+//!
+//! serde_yaml throws away comments, so instead of writing the whole
+//! config out again, only the top level keys that actually changed
+//! get replaced in the text. Comments anywhere else are left alone,
+//! and a comment at the end of a one line value (`colour: Red # <3`)
+//! is kept too.
+
+use std::ops::Range;
+
+use serde_yaml::{Mapping, Value};
+
+use super::{validate, Config};
+
+/// `text` with everything that's different in `config` replaced
+pub fn update_text(text: &str, config: &Config) -> serde_yaml::Result<String> {
+    let Value::Mapping(new) = serde_yaml::to_value(config)? else {
+        unreachable!("Configs are always maps");
+    };
+    // Compare against what the old text loads as, so things that are
+    // written differently but mean the same (`9:00` and `09:00:00`)
+    // don't count as changes
+    let old = match validate::parse(text) {
+        Ok(old) => serde_yaml::to_value(old)?,
+        Err(_) => Value::Null,
+    };
+
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    for (key, value) in &new {
+        if old.get(key) == Some(value) {
+            continue;
+        }
+        let key = key.as_str().expect("Config keys are strings");
+        let mut entry = Mapping::new();
+        entry.insert(key.into(), value.clone());
+        let mut block: Vec<String> = serde_yaml::to_string(&entry)?
+            .lines()
+            .map(String::from)
+            .collect();
+        match find_block(&lines, key) {
+            Some(range) => {
+                if let (Some(comment), [line]) =
+                    (trailing_comment(&lines[range.start]), &mut block[..])
+                {
+                    line.push(' ');
+                    line.push_str(comment);
+                }
+                lines.splice(range, block);
+            }
+            None => lines.append(&mut block),
+        }
+    }
+    // Optional things that got turned off
+    if let Some(old) = old.as_mapping() {
+        for key in old.keys().filter(|key| !new.contains_key(*key)) {
+            if let Some(range) = key.as_str().and_then(|key| find_block(&lines, key)) {
+                lines.drain(range);
+            }
+        }
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+    Ok(text)
+}
+
+/// The lines that hold a top level key and its value
+fn find_block(lines: &[String], key: &str) -> Option<Range<usize>> {
+    let start = lines.iter().position(|line| {
+        line.strip_prefix(key)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']))
+    })?;
+    let mut end = start + 1;
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        if line.starts_with([' ', '\t', '-']) {
+            end = i + 1;
+        } else if !(line.trim().is_empty() || line.starts_with('#')) {
+            break;
+        }
+    }
+    Some(start..end)
+}
+
+/// The `# comment` at the end of a `key: value` line, if there is one
+fn trailing_comment(line: &str) -> Option<&str> {
+    let (_, value) = line.split_once(':')?;
+    // Not worth properly parsing quotes for this, just give up
+    if value.contains(['"', '\'']) {
+        return None;
+    }
+    value.find(" #").map(|i| value[i + 1..].trim_end())
+}
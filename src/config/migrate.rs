@@ -0,0 +1,131 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use serde_yaml::{Mapping, Value};
+
+/// The version of the config format this build writes. Whenever the
+/// format changes, bump this and add a migration to [`MIGRATIONS`],
+/// even if there's nothing for it to do, so there's a record of what
+/// each version added
+pub const CURRENT_VERSION: u64 = 13;
+
+/// Configs from before there was a version field
+const UNVERSIONED: u64 = 1;
+
+/// `MIGRATIONS[n]` upgrades a config from version `n + 1` to `n + 2`
+const MIGRATIONS: &[fn(&mut Mapping)] = &[
+    v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7, v7_to_v8, v8_to_v9, v9_to_v10,
+    v10_to_v11, v11_to_v12, v12_to_v13,
+];
+
+/// Version 2 only added new fields (notifiers, hooks, snoozing, the
+/// HTTP API and D-Bus), which all come from the defaults
+fn v1_to_v2(_config: &mut Mapping) {}
+
+/// Version 3 made the keys configurable. Anything not in `keys` gets
+/// the same key it always had
+fn v2_to_v3(_config: &mut Mapping) {}
+
+/// Version 4 added the command palette's key, which only gets `:` if
+/// nothing else has it
+fn v3_to_v4(_config: &mut Mapping) {}
+
+/// Version 5 added keys for doing tasks early and showing what's
+/// coming up, which only get `x` and `u` if nothing else has them
+fn v4_to_v5(_config: &mut Mapping) {}
+
+/// Version 6 added the stats screen's key, which only gets `i` if
+/// nothing else has it
+fn v5_to_v6(_config: &mut Mapping) {}
+
+/// Version 7 added custom achievements, which are optional
+fn v6_to_v7(_config: &mut Mapping) {}
+
+/// Version 8 added accessories and themes, which are optional
+fn v7_to_v8(_config: &mut Mapping) {}
+
+/// Version 9 added the mood policy. Older configs keep the one they
+/// always had, even if the default changes
+fn v8_to_v9(config: &mut Mapping) {
+    config
+        .entry("mood".into())
+        .or_insert_with(|| "Normal".into());
+}
+
+/// Version 10 added amounts for tasks, which are optional, and the key
+/// for logging them, which only gets `a` if nothing else has it
+fn v9_to_v10(_config: &mut Mapping) {}
+
+/// Version 11 added medications, which are optional
+fn v10_to_v11(_config: &mut Mapping) {}
+
+/// Version 12 added guided timers for tasks, which are optional
+fn v11_to_v12(_config: &mut Mapping) {}
+
+/// Version 13 added the `NoneMissed` rule for achievements, which is
+/// optional
+fn v12_to_v13(_config: &mut Mapping) {}
+
+#[derive(Debug)]
+pub enum MigrateError {
+    NotAMap,
+    BadVersion,
+    /// Written by a newer version than we know about
+    TooNew(u64),
+}
+
+/// Upgrade a config to the current version in place, and fill in
+/// anything missing from the defaults. Returns the version it was
+/// before.
+pub fn migrate(config: &mut Value) -> Result<u64, MigrateError> {
+    let map = config.as_mapping_mut().ok_or(MigrateError::NotAMap)?;
+    let version = match map.get("version") {
+        None => UNVERSIONED,
+        Some(v) => v.as_u64().ok_or(MigrateError::BadVersion)?,
+    };
+    if version > CURRENT_VERSION {
+        return Err(MigrateError::TooNew(version));
+    }
+    if version == 0 {
+        return Err(MigrateError::BadVersion);
+    }
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        migration(map);
+    }
+    map.insert("version".into(), CURRENT_VERSION.into());
+
+    let defaults: Value = serde_yaml::from_str(include_str!("../default_config.yaml"))
+        .expect("The default config is valid");
+    merge_defaults(config, &defaults);
+    Ok(version)
+}
+
+/// Add any settings from `defaults` that are missing in `config`.
+/// This only looks at the top level, what's in maps like `keys` is
+/// up to the user (anything they leave out gets its usual value when
+/// loading), and lists like `tasks` definitely are
+fn merge_defaults(config: &mut Value, defaults: &Value) {
+    let (Some(config), Some(defaults)) = (config.as_mapping_mut(), defaults.as_mapping()) else {
+        return;
+    };
+    for (key, default) in defaults {
+        if !config.contains_key(key) {
+            config.insert(key.clone(), default.clone());
+        }
+    }
+}
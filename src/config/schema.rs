@@ -0,0 +1,275 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! JSON Schema for `config.yaml`, for editors with a YAML language
+//! server.
+//!
+//! YAML tags like `!Times` don't exist in JSON, the language server
+//! just looks at the value after the tag, so the tagged enums get
+//! written by hand here to describe what goes after each tag instead
+//! of what serde would do with them in JSON.
+
+use std::borrow::Cow;
+
+use schemars::{generate::SchemaSettings, json_schema, JsonSchema, Schema, SchemaGenerator};
+
+use super::{ColorDef, Config};
+use crate::{
+    achievements::Rule,
+    keybinds::Key,
+    notifier::NotifierConfig,
+    task::{Schedule, TaskType},
+};
+
+/// The name of the schema file, next to `config.yaml`
+pub const SCHEMA_FILE: &str = "config.schema.json";
+
+/// The comment that points the YAML language server at the schema
+pub fn modeline() -> String {
+    format!("# yaml-language-server: $schema={SCHEMA_FILE}\n")
+}
+
+/// The schema for `config.yaml`, as pretty printed JSON
+pub fn schema() -> String {
+    let mut schema = SchemaSettings::draft07()
+        .into_generator()
+        .into_root_schema_for::<Config>();
+    schema.insert("title".into(), "Tuigotchi Health config".into());
+    serde_json::to_string_pretty(&schema).expect("Schemas are always valid JSON")
+}
+
+/// Stands in for durations that are written like `30min` or `1h 30m`
+pub struct HumanDuration;
+
+impl JsonSchema for HumanDuration {
+    fn schema_name() -> Cow<'static, str> {
+        "Duration".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "A length of time, like `30min`, `2h` or `1h 30m`",
+            "pattern": r"^\s*\d+\s*[a-zA-Zµ]+(\s*\d+\s*[a-zA-Zµ]+)*\s*$",
+        })
+    }
+}
+
+impl JsonSchema for Schedule {
+    fn schema_name() -> Cow<'static, str> {
+        "Schedule".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let duration = generator.subschema_for::<HumanDuration>();
+        json_schema!({
+            "description": "When a task should be done",
+            "oneOf": [
+                {
+                    "description": "`!Times`: at these times every day",
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "string",
+                        "description": "A time of day, like `9:00` or `18:30`",
+                        "pattern": r"^\d{1,2}:\d{2}(:\d{2})?$",
+                    },
+                },
+                {
+                    "description": "`!Interval`: this long after it was last done",
+                    "allOf": [duration],
+                },
+            ],
+        })
+    }
+}
+
+impl JsonSchema for TaskType {
+    fn schema_name() -> Cow<'static, str> {
+        "TaskType".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "What kind of task it is, custom tasks are written as `!Other <name>`",
+            "type": "string",
+            "examples": [
+                "Eat",
+                "Drink",
+                "Brush Teeth",
+                "Shower",
+                "Eyes Rest",
+                "Take Meds",
+                "Sleep",
+                "Bathroom",
+            ],
+        })
+    }
+}
+
+impl JsonSchema for NotifierConfig {
+    fn schema_name() -> Cow<'static, str> {
+        "Notifier".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "A way to send notifications",
+            "oneOf": [
+                {
+                    "description": "`Desktop`: desktop notifications, or `Bell`: ring the terminal bell",
+                    "enum": ["Desktop", "Bell"],
+                },
+                {
+                    "description": "`!Terminal`: terminal escape sequences, these work over SSH",
+                    "enum": ["OSC 9", "OSC 777"],
+                },
+                {
+                    "description": "`!Command`: a shell command, the task is in $TUIGOTCHI_TASK",
+                    "type": "string",
+                },
+            ],
+        })
+    }
+}
+
+impl JsonSchema for ColorDef {
+    fn schema_name() -> Cow<'static, str> {
+        "Colour".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "A terminal colour",
+            "oneOf": [
+                {
+                    "enum": [
+                        "Reset",
+                        "Black",
+                        "DarkGrey",
+                        "Red",
+                        "DarkRed",
+                        "Green",
+                        "DarkGreen",
+                        "Yellow",
+                        "DarkYellow",
+                        "Blue",
+                        "DarkBlue",
+                        "Magenta",
+                        "DarkMagenta",
+                        "Cyan",
+                        "DarkCyan",
+                        "White",
+                        "Grey",
+                    ],
+                },
+                {
+                    "description": "`!Rgb`: any colour, on terminals that support it",
+                    "type": "object",
+                    "properties": {
+                        "r": { "type": "integer", "minimum": 0, "maximum": 255 },
+                        "g": { "type": "integer", "minimum": 0, "maximum": 255 },
+                        "b": { "type": "integer", "minimum": 0, "maximum": 255 },
+                    },
+                    "required": ["r", "g", "b"],
+                    "additionalProperties": false,
+                },
+                {
+                    "description": "`!AnsiValue`: one of the 256 ANSI colours",
+                    "type": "integer",
+                    "minimum": 0,
+                    "maximum": 255,
+                },
+            ],
+        })
+    }
+}
+
+impl JsonSchema for Key {
+    fn schema_name() -> Cow<'static, str> {
+        "Key".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "A single key, like `q`, `?` or `1`",
+            "oneOf": [
+                { "type": "string", "minLength": 1, "maxLength": 1 },
+                { "type": "integer", "minimum": 0, "maximum": 9 },
+            ],
+        })
+    }
+}
+
+impl JsonSchema for Rule {
+    fn schema_name() -> Cow<'static, str> {
+        "AchievementRule".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        let task = json_schema!({
+            "type": "string",
+            "description": "A task's name, the same as its `type`",
+        });
+        json_schema!({
+            "description": "What has to happen to unlock an achievement",
+            "oneOf": [
+                {
+                    "description": "`!Streak`: on time every time it was due, this many days in a row (every task if there's no task)",
+                    "type": "object",
+                    "properties": {
+                        "task": task,
+                        "days": { "type": "integer", "minimum": 1 },
+                    },
+                    "required": ["days"],
+                    "additionalProperties": false,
+                },
+                {
+                    "description": "`!NoneMissed`: never missed, this many days in a row, late or skipped is fine (every task if there's no task)",
+                    "type": "object",
+                    "properties": {
+                        "task": task,
+                        "days": { "type": "integer", "minimum": 1 },
+                    },
+                    "required": ["days"],
+                    "additionalProperties": false,
+                },
+                {
+                    "description": "`!InADay`: done this many times in one day",
+                    "type": "object",
+                    "properties": {
+                        "task": task,
+                        "count": { "type": "integer", "minimum": 1 },
+                    },
+                    "required": ["task", "count"],
+                    "additionalProperties": false,
+                },
+                {
+                    "description": "`!Total`: done this many times altogether (every task if there's no task)",
+                    "type": "object",
+                    "properties": {
+                        "task": task,
+                        "count": { "type": "integer", "minimum": 1 },
+                    },
+                    "required": ["count"],
+                    "additionalProperties": false,
+                },
+            ],
+        })
+    }
+}
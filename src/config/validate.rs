@@ -0,0 +1,547 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::{collections::HashMap, fmt, time::Duration};
+
+use serde_yaml::{value::TaggedValue, Value};
+use yaml_rust2::{
+    parser::{Event, MarkedEventReceiver, Parser},
+    scanner::Marker,
+};
+
+use super::{
+    migrate::{migrate, MigrateError},
+    Config,
+};
+use crate::{keybinds::Keybinds, task::TaskType};
+
+/// Something wrong with the config, and how to fix it
+#[derive(Debug, Clone)]
+pub struct ConfigProblem {
+    /// Line and column in the file, if we know them
+    pub location: Option<(usize, usize)>,
+    /// Where in the config it is, like `tasks[2].schedule`
+    pub path: String,
+    pub message: String,
+    pub suggestion: String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some((line, col)) = self.location {
+            write!(f, "{line}:{col}: ")?;
+        }
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        write!(f, "{}", self.message)?;
+        if !self.suggestion.is_empty() {
+            write!(f, "\n    help: {}", self.suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Every problem we found with the config
+#[derive(Debug, Clone)]
+pub struct ConfigErrors(pub Vec<ConfigProblem>);
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, problem) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+/// Parse and check a config, collecting everything that's wrong with
+/// it instead of stopping at the first problem
+pub fn parse(text: &str) -> Result<Config, ConfigErrors> {
+    let locations = match Locations::find(text) {
+        Ok(locations) => locations,
+        Err(e) => {
+            return Err(ConfigErrors(vec![ConfigProblem {
+                location: Some((e.marker().line(), e.marker().col() + 1)),
+                path: String::new(),
+                message: e.info().to_string(),
+                suggestion: "this isn't valid YAML, check the indentation and quotes around here"
+                    .to_string(),
+            }]))
+        }
+    };
+    let mut value: Value = serde_yaml::from_str(text).map_err(|e| {
+        ConfigErrors(vec![ConfigProblem {
+            location: e.location().map(|l| (l.line(), l.column())),
+            path: String::new(),
+            message: e.to_string(),
+            suggestion: String::new(),
+        }])
+    })?;
+    if let Err(e) = migrate(&mut value) {
+        let (path, message, suggestion) = match e {
+            MigrateError::NotAMap => (
+                "",
+                "the config should be a list of settings".to_string(),
+                "compare this with the default config",
+            ),
+            MigrateError::BadVersion => (
+                "version",
+                "the version should be a whole number above 0".to_string(),
+                "remove the version line if you're not sure",
+            ),
+            MigrateError::TooNew(version) => (
+                "version",
+                format!("this config is for a newer version of Tuigotchi Health (config version {version})"),
+                "update Tuigotchi Health",
+            ),
+        };
+        return Err(ConfigErrors(vec![ConfigProblem {
+            location: locations.get(path),
+            path: path.to_string(),
+            message,
+            suggestion: suggestion.to_string(),
+        }]));
+    }
+
+    let mut checker = Checker {
+        locations,
+        problems: Vec::new(),
+    };
+    checker.check(&value);
+    if !checker.problems.is_empty() {
+        return Err(ConfigErrors(checker.problems));
+    }
+
+    // Anything the checks above didn't catch, serde will
+    let config: Config = serde_yaml::from_value(value).map_err(|e| {
+        // Errors from the upgraded config don't know where in the file
+        // they are, but parsing the file as it is does, unless the
+        // problem was just that things were missing
+        let problem = match serde_yaml::from_str::<Config>(text) {
+            Err(located) if !located.to_string().starts_with("missing field") => ConfigProblem {
+                location: located.location().map(|l| (l.line(), l.column())),
+                path: String::new(),
+                // We already show where it is
+                message: located
+                    .to_string()
+                    .split_once(" at line ")
+                    .map_or(located.to_string(), |(m, _)| m.to_string()),
+                suggestion: "compare this with the default config".to_string(),
+            },
+            _ => ConfigProblem {
+                location: None,
+                path: String::new(),
+                message: e.to_string(),
+                suggestion: "compare this with the default config".to_string(),
+            },
+        };
+        ConfigErrors(vec![problem])
+    })?;
+
+    // Keys can only be checked once we know all the tasks
+    if let Err(conflicts) = Keybinds::new(&config.keys, &config.tasks_with_meds()) {
+        return Err(ConfigErrors(
+            conflicts
+                .into_iter()
+                .map(|conflict| ConfigProblem {
+                    location: checker.locations.get(&conflict.path),
+                    path: conflict.path,
+                    message: conflict.message,
+                    suggestion: conflict.suggestion,
+                })
+                .collect(),
+        ));
+    }
+    Ok(config)
+}
+
+struct Checker {
+    locations: Locations,
+    problems: Vec<ConfigProblem>,
+}
+
+impl Checker {
+    fn problem(&mut self, path: &str, message: impl Into<String>, suggestion: impl Into<String>) {
+        self.problems.push(ConfigProblem {
+            location: self.locations.get(path),
+            path: path.to_string(),
+            message: message.into(),
+            suggestion: suggestion.into(),
+        });
+    }
+
+    fn duration(&self, value: &Value, key: &str) -> Option<Duration> {
+        humantime::parse_duration(value.get(key)?.as_str()?).ok()
+    }
+
+    fn check(&mut self, config: &Value) {
+        if let (Some(timeout), Some(max)) = (
+            self.duration(config, "task_timeout"),
+            self.duration(config, "task_timeout_max"),
+        ) {
+            if timeout > max {
+                self.problem(
+                    "task_timeout",
+                    format!(
+                        "task_timeout ({}) is longer than task_timeout_max ({})",
+                        humantime::format_duration(timeout),
+                        humantime::format_duration(max)
+                    ),
+                    "make task_timeout_max at least as long as task_timeout",
+                );
+            }
+        }
+        if let (Some(min), Some(max)) = (
+            self.duration(config, "idle_animation_time_min"),
+            self.duration(config, "idle_animation_time_max"),
+        ) {
+            if min >= max {
+                self.problem(
+                    "idle_animation_time_min",
+                    "idle_animation_time_min needs to be shorter than idle_animation_time_max",
+                    "swap them, or make idle_animation_time_max longer",
+                );
+            }
+        }
+
+        let Some(tasks) = config.get("tasks").and_then(Value::as_sequence) else {
+            return;
+        };
+        let mut seen: HashMap<TaskType, usize> = HashMap::new();
+        for (i, task) in tasks.iter().enumerate() {
+            let path = format!("tasks[{i}]");
+            if let Some(ty) = self.check_task_type(&path, task.get("type")) {
+                if let Some(first) = seen.get(&ty) {
+                    self.problem(
+                        &format!("{path}.type"),
+                        format!("there's already a {} task at tasks[{first}]", ty.name()),
+                        "combine their schedules into one task",
+                    );
+                } else {
+                    seen.insert(ty, i);
+                }
+            }
+            if let Some(schedule) = task.get("schedule") {
+                self.check_schedule(&format!("{path}.schedule"), schedule);
+            }
+            if let Some(amount) = task.get("amount") {
+                self.check_amount(&format!("{path}.amount"), amount);
+            }
+            if let Some(guide) = task.get("guide") {
+                self.check_guide(&format!("{path}.guide"), guide);
+            }
+        }
+
+        // Medications are tasks too, by name, so they can't share one.
+        // Names that only differ by case would be too easy to mix up
+        let meds = config.get("medications").and_then(Value::as_sequence);
+        let mut seen_meds: HashMap<String, usize> = HashMap::new();
+        for (i, med) in meds.into_iter().flatten().enumerate() {
+            let path = format!("medications[{i}]");
+            if let Some(name) = med.get("name").and_then(Value::as_str) {
+                let same = |other: &str| other.trim().eq_ignore_ascii_case(name.trim());
+                if let Some(known) = BUILT_IN_TASKS.into_iter().find(|known| same(known)) {
+                    self.problem(
+                        &format!("{path}.name"),
+                        format!("`{known}` is already a kind of task"),
+                        "use the medication's name, like `Sertraline`",
+                    );
+                } else if let Some((ty, first)) = seen.iter().find(|(ty, _)| same(ty.name())) {
+                    self.problem(
+                        &format!("{path}.name"),
+                        format!(
+                            "there's already a task called `{}` at tasks[{first}]",
+                            ty.name()
+                        ),
+                        "give the medication a different name",
+                    );
+                } else if let Some(first) = seen_meds.insert(name.trim().to_lowercase(), i) {
+                    self.problem(
+                        &format!("{path}.name"),
+                        format!("there's already a `{name}` at medications[{first}]"),
+                        "combine their schedules into one",
+                    );
+                }
+            }
+            if let Some(schedule) = med.get("schedule") {
+                self.check_schedule(&format!("{path}.schedule"), schedule);
+            }
+            for key in ["pills", "per_dose"] {
+                if med.get(key).and_then(Value::as_u64) == Some(0) {
+                    self.problem(
+                        &format!("{path}.{key}"),
+                        format!("`{key}` can't be zero"),
+                        "leave `pills` out if you don't want them counted",
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_task_type(&mut self, path: &str, ty: Option<&Value>) -> Option<TaskType> {
+        let path = format!("{path}.type");
+        match ty? {
+            Value::String(name) => {
+                let ty = TaskType::from_name(name);
+                if let TaskType::Other(_) = ty {
+                    let suggestion = match closest_task_type(name) {
+                        Some(close) => format!(
+                            "did you mean `{close}`? Custom tasks are written as `!Other {name}`"
+                        ),
+                        None => format!("custom tasks are written as `!Other {name}`"),
+                    };
+                    self.problem(&path, format!("unknown task type `{name}`"), suggestion);
+                    None
+                } else {
+                    Some(ty)
+                }
+            }
+            Value::Tagged(tagged) if tagged.tag == "Other" => {
+                let name = tagged.value.as_str()?;
+                // Tasks go by their name everywhere (the history, keys,
+                // the HTTP API...), so it would get mixed up with the
+                // built in one
+                let same = |other: &str| other.trim().eq_ignore_ascii_case(name.trim());
+                if let Some(known) = BUILT_IN_TASKS.into_iter().find(|known| same(known)) {
+                    self.problem(
+                        &path,
+                        format!("`{known}` is already a kind of task"),
+                        format!("write it as just `{known}`, or give it a different name"),
+                    );
+                    return None;
+                }
+                Some(TaskType::Other(name.to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    fn check_schedule(&mut self, path: &str, schedule: &Value) {
+        let Value::Tagged(tagged) = schedule else {
+            return;
+        };
+        let TaggedValue { tag, value } = tagged.as_ref();
+        if tag == "Times" {
+            let empty = match value {
+                Value::Sequence(times) => times.is_empty(),
+                Value::Null => true,
+                _ => false,
+            };
+            if empty {
+                self.problem(
+                    path,
+                    "`!Times` needs at least one time",
+                    "add a time on the next line, like `- 9:00`, or use `!Interval 1h` instead",
+                );
+            }
+        } else if tag == "Interval" {
+            let zero = value
+                .as_str()
+                .and_then(|s| humantime::parse_duration(s).ok())
+                .is_some_and(|d| d.is_zero());
+            if zero {
+                self.problem(
+                    path,
+                    "`!Interval` can't be zero",
+                    "use a length of time, like `!Interval 30min`",
+                );
+            }
+        }
+    }
+
+    fn check_guide(&mut self, path: &str, guide: &Value) {
+        if self
+            .duration(guide, "duration")
+            .is_some_and(|d| d.is_zero())
+        {
+            self.problem(
+                &format!("{path}.duration"),
+                "the guide can't be over before it starts",
+                "use a length of time, like `duration: 20s`",
+            );
+        }
+        let steps = guide.get("steps").and_then(Value::as_sequence);
+        for (i, step) in steps.into_iter().flatten().enumerate() {
+            if self.duration(step, "duration").is_some_and(|d| d.is_zero()) {
+                self.problem(
+                    &format!("{path}.steps[{i}].duration"),
+                    "steps can't be zero",
+                    "use a length of time, like `duration: 4s`",
+                );
+            }
+        }
+    }
+
+    fn check_amount(&mut self, path: &str, amount: &Value) {
+        for (key, example) in [("per", "250"), ("target", "2000")] {
+            if amount.get(key).and_then(Value::as_u64) == Some(0) {
+                self.problem(
+                    &format!("{path}.{key}"),
+                    format!("`{key}` can't be zero"),
+                    format!("use how much you'd have, like `{key}: {example}`"),
+                );
+            }
+        }
+    }
+}
+
+/// Every kind of task except `Other`, by name
+const BUILT_IN_TASKS: [&str; 8] = [
+    "Eat",
+    "Drink",
+    "Brush Teeth",
+    "Shower",
+    "Eyes Rest",
+    "Take Meds",
+    "Sleep",
+    "Bathroom",
+];
+
+fn closest_task_type(name: &str) -> Option<&'static str> {
+    BUILT_IN_TASKS
+        .into_iter()
+        .map(|known| {
+            let score =
+                strsim::normalized_damerau_levenshtein(&name.to_lowercase(), &known.to_lowercase());
+            (known, score)
+        })
+        .filter(|(_, score)| *score >= 0.5)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(known, _)| known)
+}
+
+/// Where each value in the file is, keyed by paths like
+/// `tasks[2].schedule`, since serde_yaml doesn't keep track of that
+#[derive(Default)]
+struct Locations {
+    paths: HashMap<String, (usize, usize)>,
+    stack: Vec<Frame>,
+}
+
+enum Frame {
+    Map {
+        path: String,
+        key: Option<(String, Marker)>,
+    },
+    Seq {
+        path: String,
+        index: usize,
+    },
+}
+
+impl Locations {
+    fn find(text: &str) -> Result<Self, yaml_rust2::ScanError> {
+        let mut locations = Locations::default();
+        Parser::new_from_str(text).load(&mut locations, false)?;
+        Ok(locations)
+    }
+
+    fn get(&self, path: &str) -> Option<(usize, usize)> {
+        self.paths.get(path).copied()
+    }
+
+    /// Work out the path of a new value and remember where it is
+    fn node(&mut self, mut mark: Marker) -> String {
+        let path = match self.stack.last_mut() {
+            None => String::new(),
+            Some(Frame::Map { path, key }) => {
+                // Point at the key, empty values don't have a useful
+                // location of their own
+                let (key, key_mark) = key.take().unwrap_or((String::new(), mark));
+                mark = key_mark;
+                if path.is_empty() {
+                    key
+                } else {
+                    format!("{path}.{key}")
+                }
+            }
+            Some(Frame::Seq { path, index }) => {
+                *index += 1;
+                format!("{path}[{}]", *index - 1)
+            }
+        };
+        self.paths
+            .insert(path.clone(), (mark.line(), mark.col() + 1));
+        path
+    }
+}
+
+impl MarkedEventReceiver for Locations {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        match ev {
+            Event::Scalar(value, ..) => {
+                // Scalars are either the key or the value in a map
+                if let Some(Frame::Map {
+                    key: key @ None, ..
+                }) = self.stack.last_mut()
+                {
+                    *key = Some((value, mark));
+                } else {
+                    self.node(mark);
+                }
+            }
+            Event::Alias(_) => {
+                self.node(mark);
+            }
+            Event::SequenceStart(..) => {
+                let path = self.node(mark);
+                self.stack.push(Frame::Seq { path, index: 0 });
+            }
+            Event::MappingStart(..) => {
+                let path = self.node(mark);
+                self.stack.push(Frame::Map { path, key: None });
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(text: &str) -> Vec<ConfigProblem> {
+        parse(text).err().map(|e| e.0).unwrap_or_default()
+    }
+
+    #[test]
+    fn custom_tasks_cant_be_built_in_ones() {
+        for name in ["Eat", "drink", " Brush teeth "] {
+            let problems = problems(&format!(
+                "version: 12\ntasks:\n  - type: !Other \"{name}\"\n    schedule: !Interval 1h\n"
+            ));
+            assert!(
+                problems.iter().any(|p| p.path == "tasks[0].type"),
+                "`!Other {name}` was allowed: {problems:?}"
+            );
+        }
+        assert!(problems(
+            "version: 12\ntasks:\n  - type: !Other Stretch\n    schedule: !Interval 1h\n"
+        )
+        .is_empty());
+    }
+}
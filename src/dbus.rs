@@ -0,0 +1,158 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::time::Duration;
+
+use color_eyre::Result;
+use zbus::{blocking::Connection, fdo, interface, SignalContext};
+
+use crate::{
+    action::{Action, MAX_SNOOZE_SECONDS},
+    remote::RemoteHandle,
+    task::TaskType,
+    task_manager::{TaskDue, Tasks},
+};
+
+const BUS_NAME: &str = "ca.vedapowered.TuigotchiHealth";
+const OBJECT_PATH: &str = "/ca/vedapowered/TuigotchiHealth";
+
+/// A task as it's sent over D-Bus: (task, state, due as unix seconds),
+/// where state is one of "past", "current" or "upcoming"
+type DbusTask = (String, String, i64);
+
+struct TuigotchiService {
+    remote: RemoteHandle,
+}
+
+impl TuigotchiService {
+    fn task_type(&self, task: &str) -> fdo::Result<TaskType> {
+        let ty = TaskType::from_name(task);
+        if self.remote.status().has_task(&ty) {
+            Ok(ty)
+        } else {
+            Err(fdo::Error::InvalidArgs(format!("No such task: {task}")))
+        }
+    }
+
+    fn send(&self, action: Action) -> fdo::Result<()> {
+        if self.remote.send(action) {
+            Ok(())
+        } else {
+            Err(fdo::Error::Failed("Shutting down".to_string()))
+        }
+    }
+}
+
+#[interface(name = "ca.vedapowered.TuigotchiHealth")]
+impl TuigotchiService {
+    fn complete(&self, task: &str) -> fdo::Result<()> {
+        let ty = self.task_type(task)?;
+        self.send(Action::Complete(ty, None))
+    }
+
+    fn snooze(&self, task: &str, seconds: u64) -> fdo::Result<()> {
+        let ty = self.task_type(task)?;
+        if seconds > MAX_SNOOZE_SECONDS {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Can't snooze for more than {MAX_SNOOZE_SECONDS} seconds"
+            )));
+        }
+        self.send(Action::Snooze(ty, Some(Duration::from_secs(seconds))))
+    }
+
+    /// Returns (name, mood, happiness, tasks)
+    fn get_status(&self) -> (String, String, f64, Vec<DbusTask>) {
+        let status = self.remote.status();
+        (
+            status.name,
+            status.mood,
+            status.happiness as f64,
+            dbus_tasks(&status.tasks),
+        )
+    }
+
+    #[zbus(signal)]
+    async fn task_due(ctxt: &SignalContext<'_>, task: &str, due: i64) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn task_overdue(ctxt: &SignalContext<'_>, task: &str, due: i64) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn mood_changed(ctxt: &SignalContext<'_>, old: &str, new: &str) -> zbus::Result<()>;
+}
+
+fn dbus_tasks(tasks: &Tasks) -> Vec<DbusTask> {
+    [
+        ("past", &tasks.past),
+        ("current", &tasks.current),
+        ("upcoming", &tasks.upcoming),
+    ]
+    .into_iter()
+    .flat_map(|(state, tasks)| {
+        tasks.iter().map(move |t| {
+            (
+                t.ty.name().to_string(),
+                state.to_string(),
+                t.when.timestamp(),
+            )
+        })
+    })
+    .collect()
+}
+
+/// The lil guy on the session bus, so desktop extensions and scripts
+/// can see what's going on and complete or snooze tasks
+pub struct DbusService {
+    conn: Connection,
+}
+
+impl DbusService {
+    pub fn new(remote: RemoteHandle) -> Result<Self> {
+        let conn = zbus::blocking::connection::Builder::session()?
+            .name(BUS_NAME)?
+            .serve_at(OBJECT_PATH, TuigotchiService { remote })?
+            .build()?;
+        Ok(DbusService { conn })
+    }
+
+    fn signal_context(&self) -> Result<SignalContext<'static>> {
+        Ok(self
+            .conn
+            .object_server()
+            .interface::<_, TuigotchiService>(OBJECT_PATH)?
+            .signal_context()
+            .clone())
+    }
+
+    pub fn task_due(&self, task: &TaskDue, is_overdue: bool) -> Result<()> {
+        let ctxt = self.signal_context()?;
+        let (name, due) = (task.ty.name(), task.when.timestamp());
+        if is_overdue {
+            zbus::block_on(TuigotchiService::task_overdue(&ctxt, name, due))?;
+        } else {
+            zbus::block_on(TuigotchiService::task_due(&ctxt, name, due))?;
+        }
+        Ok(())
+    }
+
+    pub fn mood_changed(&self, old: &str, new: &str) -> Result<()> {
+        let ctxt = self.signal_context()?;
+        zbus::block_on(TuigotchiService::mood_changed(&ctxt, old, new))?;
+        Ok(())
+    }
+}
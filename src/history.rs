@@ -0,0 +1,153 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! Every time a task gets done (or skipped), so there's something to
+//! make stats out of. It's a file with one JSON object per line, new
+//! ones just get added to the end.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, NaiveDate};
+use color_eyre::Result;
+use log::warn;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::task::TaskType;
+
+/// How it went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// Done before the task timed out (or early)
+    OnTime,
+    /// Done after it timed out, but before it was due again
+    Late,
+    /// Not done until it was already due again
+    Missed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Completion {
+    #[serde(
+        serialize_with = "serialize_task",
+        deserialize_with = "deserialize_task"
+    )]
+    pub task: TaskType,
+    /// When it was supposed to be done
+    pub due: DateTime<Local>,
+    /// When it actually got done
+    pub at: DateTime<Local>,
+    pub status: Status,
+    /// How much, for tasks that keep track of an amount
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u32>,
+}
+
+fn serialize_task<S: Serializer>(ty: &TaskType, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(ty.name())
+}
+
+fn deserialize_task<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TaskType, D::Error> {
+    Ok(TaskType::from_name(&String::deserialize(deserializer)?))
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    path: PathBuf,
+    completions: Vec<Completion>,
+}
+
+impl History {
+    const FILE: &str = "history.jsonl";
+
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join(Self::FILE)
+    }
+
+    /// Read the history, not having one yet is fine. Lines that can't
+    /// be read are skipped, losing one is better than losing all of it
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let mut completions = Vec::new();
+        match File::open(&path) {
+            Ok(file) => {
+                for (i, line) in BufReader::new(file).lines().enumerate() {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str(&line) {
+                        Ok(completion) => completions.push(completion),
+                        Err(e) => warn!("Skipping line {} of {}: {e}", i + 1, path.display()),
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        Ok(History { path, completions })
+    }
+
+    /// One that only lives in memory
+    #[cfg(test)]
+    pub fn with(completions: Vec<Completion>) -> Self {
+        History {
+            path: PathBuf::new(),
+            completions,
+        }
+    }
+
+    pub fn completions(&self) -> &[Completion] {
+        &self.completions
+    }
+
+    /// How much of a task got done on a day, for tasks that keep track
+    /// of an amount
+    pub fn amount_on(&self, ty: &TaskType, day: NaiveDate) -> u32 {
+        self.completions
+            .iter()
+            .filter(|c| &c.task == ty && c.at.date_naive() == day)
+            .filter_map(|c| c.amount)
+            .fold(0, u32::saturating_add)
+    }
+
+    /// Remember a completion, and save it. Not being able to save
+    /// shouldn't stop the task from getting done, so that's just logged
+    pub fn record(&mut self, completion: Completion) {
+        if let Err(e) = self.append(&completion) {
+            warn!("Couldn't save to {}: {e}", self.path.display());
+        }
+        self.completions.push(completion);
+    }
+
+    fn append(&self, completion: &Completion) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(completion)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::{
+    io::Write,
+    process::{Child, Stdio},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+use color_eyre::Result;
+use log::{info, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::notifier::shell_command;
+
+/// User commands to run when things happen to the lil guy
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct HooksConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_due: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_overdue: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_complete: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_snooze: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_mood_change: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_start: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_exit: Option<String>,
+    /// Hooks still running after this long get killed
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "crate::config::HumanDuration")]
+    pub timeout: Duration,
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        HooksConfig {
+            on_due: None,
+            on_overdue: None,
+            on_complete: None,
+            on_snooze: None,
+            on_mood_change: None,
+            on_start: None,
+            on_exit: None,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookKind {
+    Due,
+    Overdue,
+    Complete,
+    Snooze,
+    MoodChange,
+    Start,
+    Exit,
+}
+
+impl HookKind {
+    fn name(&self) -> &'static str {
+        match self {
+            HookKind::Due => "due",
+            HookKind::Overdue => "overdue",
+            HookKind::Complete => "complete",
+            HookKind::Snooze => "snooze",
+            HookKind::MoodChange => "mood_change",
+            HookKind::Start => "start",
+            HookKind::Exit => "exit",
+        }
+    }
+}
+
+/// Everything a hook gets told about, this is sent as JSON on stdin,
+/// and as `TUIGOTCHI_*` environment variables
+#[derive(Debug, Clone, Serialize)]
+pub struct HookEvent {
+    pub event: HookKind,
+    pub time: DateTime<Local>,
+    pub mood: String,
+    pub happiness: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due: Option<DateTime<Local>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<DateTime<Local>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_mood: Option<String>,
+}
+
+impl HookEvent {
+    pub fn new(event: HookKind, mood: &str, happiness: f32) -> Self {
+        HookEvent {
+            event,
+            time: Local::now(),
+            mood: mood.to_string(),
+            happiness,
+            task: None,
+            due: None,
+            snoozed_until: None,
+            previous_mood: None,
+        }
+    }
+
+    fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = vec![
+            ("TUIGOTCHI_EVENT", self.event.name().to_string()),
+            ("TUIGOTCHI_TIME", self.time.to_rfc3339()),
+            ("TUIGOTCHI_MOOD", self.mood.clone()),
+            ("TUIGOTCHI_HAPPINESS", self.happiness.to_string()),
+        ];
+        if let Some(task) = &self.task {
+            env.push(("TUIGOTCHI_TASK", task.clone()));
+        }
+        if let Some(due) = &self.due {
+            env.push(("TUIGOTCHI_DUE", due.to_rfc3339()));
+        }
+        if let Some(until) = &self.snoozed_until {
+            env.push(("TUIGOTCHI_SNOOZED_UNTIL", until.to_rfc3339()));
+        }
+        if let Some(mood) = &self.previous_mood {
+            env.push(("TUIGOTCHI_PREVIOUS_MOOD", mood.clone()));
+        }
+        env
+    }
+}
+
+pub struct Hooks {
+    config: HooksConfig,
+}
+
+impl Hooks {
+    pub fn new(config: HooksConfig) -> Self {
+        Hooks { config }
+    }
+
+    fn command(&self, kind: HookKind) -> Option<&str> {
+        match kind {
+            HookKind::Due => self.config.on_due.as_deref(),
+            HookKind::Overdue => self.config.on_overdue.as_deref(),
+            HookKind::Complete => self.config.on_complete.as_deref(),
+            HookKind::Snooze => self.config.on_snooze.as_deref(),
+            HookKind::MoodChange => self.config.on_mood_change.as_deref(),
+            HookKind::Start => self.config.on_start.as_deref(),
+            HookKind::Exit => self.config.on_exit.as_deref(),
+        }
+    }
+
+    /// Run the hook for an event in the background, if there is one
+    pub fn run(&self, event: HookEvent) -> Option<JoinHandle<()>> {
+        let command = self.command(event.event)?.to_string();
+        let timeout = self.config.timeout;
+        Some(std::thread::spawn(move || {
+            if let Err(e) = run_hook(&command, &event, timeout) {
+                warn!("Hook for {} failed: {e}", event.event.name());
+            }
+        }))
+    }
+
+    /// Run the hook for an event and wait for it to finish (or time
+    /// out), for when we're about to exit and can't leave it behind
+    pub fn run_blocking(&self, event: HookEvent) {
+        if let Some(handle) = self.run(event) {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn run_hook(command: &str, event: &HookEvent, timeout: Duration) -> Result<()> {
+    let mut cmd = shell_command(command);
+    // In its own process group, so anything it starts can be killed
+    // along with it
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+    let mut child = cmd
+        .envs(event.env())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The hook not reading its stdin is perfectly fine
+        let _ = serde_json::to_writer(&mut stdin, event);
+        let _ = stdin.write_all(b"\n");
+    }
+    let end = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            if !status.success() {
+                warn!("Hook {command:?} exited with {status}");
+            }
+            return Ok(());
+        }
+        if Instant::now() > end {
+            info!("Hook {command:?} timed out, killing it");
+            kill(&mut child)?;
+            child.wait()?;
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Kill a hook and everything it started, not just the shell
+fn kill(child: &mut Child) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        // The hook's process group has the same ID as the shell
+        let group = child.id() as libc::pid_t;
+        // SAFETY: kill doesn't touch any memory, and the group is ours
+        // since the shell hasn't been waited on yet
+        if unsafe { libc::kill(-group, libc::SIGKILL) } == 0 {
+            return Ok(());
+        }
+    }
+    child.kill()
+}
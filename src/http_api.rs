@@ -0,0 +1,209 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::{
+    io::Cursor,
+    net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use color_eyre::{eyre::eyre, Result};
+use log::{info, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{
+    action::{Action, MAX_AMOUNT, MAX_SNOOZE_SECONDS},
+    remote::RemoteHandle,
+    task::TaskType,
+};
+
+/// The HTTP API is only turned on if this is in the config
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct HttpApiConfig {
+    pub port: u16,
+    /// Requests need an `Authorization: Bearer <token>` header with
+    /// this in it
+    pub token: String,
+}
+
+/// A small JSON API on localhost, so other things (home automation,
+/// scripts, etc.) can see how the lil guy is doing and complete or
+/// snooze tasks
+///
+/// - `GET /status`: name, mood and all the tasks
+/// - `GET /tasks`: just the tasks
+/// - `POST /tasks/<task>/complete?amount=<n>`: the amount is optional,
+///   and can be from 1 up to 999999
+/// - `POST /tasks/<task>/snooze?seconds=<n>`: seconds are optional, and
+///   can be up to a year
+pub struct HttpApi {
+    config: HttpApiConfig,
+    server: Arc<Server>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HttpApi {
+    pub fn new(config: &HttpApiConfig, remote: RemoteHandle) -> Result<Self> {
+        if config.token.is_empty() {
+            return Err(eyre!("The HTTP API needs a token!"));
+        }
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, config.port));
+        let server = Arc::new(Server::http(addr).map_err(|e| eyre!("{e}"))?);
+        info!("HTTP API listening on {addr}");
+        let thread = {
+            let server = server.clone();
+            let token = config.token.clone();
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    let response = handle_request(&request, &token, &remote);
+                    if let Err(e) = request.respond(response) {
+                        warn!("Failed to respond to HTTP request: {e}");
+                    }
+                }
+            })
+        };
+        Ok(HttpApi {
+            config: config.clone(),
+            server,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn config(&self) -> &HttpApiConfig {
+        &self.config
+    }
+}
+
+impl Drop for HttpApi {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+}
+
+fn json(status: u16, body: &impl Serialize) -> Response<Cursor<Vec<u8>>> {
+    Response::from_data(serde_json::to_vec(body).unwrap_or_default())
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap())
+}
+
+fn error(status: u16, message: &str) -> Response<Cursor<Vec<u8>>> {
+    json(status, &ErrorBody { error: message })
+}
+
+fn handle_request(
+    request: &Request,
+    token: &str,
+    remote: &RemoteHandle,
+) -> Response<Cursor<Vec<u8>>> {
+    let authorized = request.headers().iter().any(|header| {
+        header.field.equiv("Authorization")
+            && header
+                .value
+                .as_str()
+                .strip_prefix("Bearer ")
+                .is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes()))
+    });
+    if !authorized {
+        return error(401, "Missing or wrong token");
+    }
+
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let segments: Vec<_> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match (request.method(), segments.as_slice()) {
+        (Method::Get, ["status"]) => json(200, &remote.status()),
+        (Method::Get, ["tasks"]) => json(200, &remote.status().tasks),
+        (Method::Post, ["tasks", task, command]) => {
+            let Some(task) = percent_decode(task) else {
+                return error(400, "Bad task name");
+            };
+            let ty = TaskType::from_name(&task);
+            if !remote.status().has_task(&ty) {
+                return error(404, "No such task");
+            }
+            let action = match *command {
+                "complete" => {
+                    let amount = query
+                        .split('&')
+                        .find_map(|param| param.strip_prefix("amount="))
+                        .map(str::parse::<u32>);
+                    match amount {
+                        Some(Ok(n @ 1..=MAX_AMOUNT)) => Action::Complete(ty, Some(n)),
+                        Some(_) => return error(400, "Bad amount"),
+                        None => Action::Complete(ty, None),
+                    }
+                }
+                "snooze" => {
+                    let seconds = query
+                        .split('&')
+                        .find_map(|param| param.strip_prefix("seconds="))
+                        .map(str::parse::<u64>);
+                    match seconds {
+                        Some(Ok(s)) if s <= MAX_SNOOZE_SECONDS => {
+                            Action::Snooze(ty, Some(Duration::from_secs(s)))
+                        }
+                        Some(_) => return error(400, "Bad number of seconds"),
+                        None => Action::Snooze(ty, None),
+                    }
+                }
+                _ => return error(404, "Not found"),
+            };
+            if remote.send(action) {
+                json(202, &remote.status())
+            } else {
+                error(503, "Shutting down")
+            }
+        }
+        (_, ["status" | "tasks"] | ["tasks", _, _]) => error(405, "Method not allowed"),
+        _ => error(404, "Not found"),
+    }
+}
+
+/// Compare without stopping at the first difference, so how long a
+/// wrong token takes to turn away doesn't give away the right one
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Decode `%XX` escapes, so tasks with spaces in their names work.
+/// This is for the path, where `+` is just a `+`
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        bytes.push(match b {
+            b'%' => {
+                let hex = [iter.next()?, iter.next()?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?
+            }
+            b => b,
+        });
+    }
+    String::from_utf8(bytes).ok()
+}
@@ -19,60 +19,153 @@ along with Tuigotchi Health. If not, see
 use std::{
     collections::{BTreeMap, VecDeque},
     io::Write,
+    ops::Range,
     path::PathBuf,
     time::{Duration, Instant},
 };
 
-use chrono::Local;
-use color_eyre::Result;
+use canvas::{Canvas, Screen};
+use chrono::{DateTime, Local};
+use color_eyre::{eyre::eyre, Result};
 use crossterm::{
-    cursor::{self, MoveTo},
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
-    execute, queue,
-    style::{self, Print, StyledContent, Stylize},
+    cursor,
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    style::{self, StyledContent, Stylize},
     terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use lil_guy::LilGuyState;
-use log::info;
-#[cfg(all(unix, not(target_os = "macos")))]
-use notify_rust::NotificationHandle;
-#[cfg(all(unix, not(target_os = "macos")))]
-use notify_rust::{Hint, Urgency};
+use guide::GuideScreen;
+use layout::Layout;
+use lil_guy::{LilGuyAnimation, LilGuyState};
+use log::{info, warn};
+use palette::CommandPalette;
 use playback_rs::{Player, Song};
 use rand::{self, seq::SliceRandom};
+use settings::{Outcome, SettingsScreen};
+use stats::StatsScreen;
 
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::dbus::DbusService;
 use crate::{
+    achievements::Achievements,
+    action::Action,
     config::Config,
-    task::TaskType,
-    task_manager::{TaskManager, Tasks},
+    hooks::{HookEvent, HookKind, Hooks},
+    http_api::HttpApi,
+    keybinds::{Binding, Key, Keybinds},
+    meds::Meds,
+    mood::MoodPolicy,
+    needs::{self, Needs},
+    notifier::{Notifier, Notifiers},
+    pet::{Pet, Stage},
+    remote::{PetStatus, Remote},
+    scheduler::{Scheduler, Waker},
+    task::{Amount, TaskType},
+    task_manager::{TaskDue, TaskManager, Tasks},
+    unlockables::{Appearance, Progress, Unlocks},
 };
 
+mod canvas;
+mod guide;
+mod layout;
 mod lil_guy;
-
-const NOTIFY_APPNAME: &str = "tuigotchi-health";
+mod meters;
+mod palette;
+mod settings;
+mod stats;
+mod upcoming;
 
 pub struct InterfaceState {
+    screen: Screen,
     lil_guy: LilGuyState,
     tasks: Tasks,
-    keybinds: BTreeMap<char, TaskType>,
+    keybinds: Keybinds,
     task_timeout: Duration,
     task_timeout_max: Duration,
     task_animations: VecDeque<TaskType>,
     current_task_animation: Option<(TaskType, Instant)>,
+    /// What to say about newly unlocked things, celebrated one at a
+    /// time once the task animations are done
+    celebrations: VecDeque<(String, LilGuyAnimation)>,
+    current_celebration: Option<(String, LilGuyAnimation, Instant)>,
+    achievements: Achievements,
+    unlocks: Unlocks,
+    pet: Pet,
+    meds: Meds,
     task_animation_duration: Duration,
     mood: StyledContent<&'static str>,
     char_name: String,
-    temp_icon_path: PathBuf,
+    notifiers: Notifiers,
+    hooks: Hooks,
+    remote: Remote,
+    http_api: Option<HttpApi>,
     #[cfg(all(unix, not(target_os = "macos")))]
-    notifications: Vec<(TaskType, NotificationHandle)>,
+    dbus: Option<DbusService>,
+    happiness: f32,
+    /// Worked out along with the happiness
+    needs: Needs,
+    mood_policy: Box<dyn MoodPolicy>,
+    snooze_duration: Duration,
+    /// Set after pressing 'z', the next task key snoozes instead
+    snoozing: bool,
+    /// Set after pressing 'x', the next task key does it even if it's
+    /// not due yet
+    doing_early: bool,
+    /// Set after pressing 'a', the next task key asks how much
+    logging_amount: bool,
+    /// How much is being typed in, for the task picked after 'a'
+    amount_input: Option<(TaskType, String)>,
+    /// Tasks that keep track of an amount, with how much is done today
+    amounts: Vec<(TaskType, Amount, u32)>,
+    /// Show what's coming up down the side
+    show_upcoming: bool,
     temp_meow_paths: Vec<PathBuf>,
-    player: Player,
+    /// Nothing to play meows on if there's no sound card
+    player: Option<Player>,
     text_colour: crossterm::style::Color,
     task_colour: crossterm::style::Color,
+    /// Shown at the top when the config couldn't be reloaded
+    config_error: Option<String>,
+    /// Shown at the top when the config picked something that's still
+    /// locked, it's running with the usual ones instead
+    locked_notice: Option<String>,
+    /// How the config says the lil guy should look, some of it might
+    /// still be locked
+    picked: Appearance,
+    config_path: PathBuf,
+    /// Set when the settings screen saves the config, which it's
+    /// already applied
+    saved_config: bool,
+    /// Open after pressing 'o', covers up everything else
+    settings: Option<SettingsScreen>,
+    /// Show every key over the top of everything
+    show_help: bool,
+    /// Open after pressing 'i', covers up everything else
+    stats: Option<StatsScreen>,
+    /// Open after pressing ':'
+    palette: Option<CommandPalette>,
+    /// Open after doing a task with a guided timer, covers up
+    /// everything else
+    guide: Option<GuideScreen>,
+    /// No notifications or sounds, tasks still show up here
+    do_not_disturb: bool,
+    /// When the tasks need working out again, if they're worked out
+    next_task_change: Option<DateTime<Local>>,
 }
 
 impl InterfaceState {
-    pub fn new(conf: &Config) -> Result<Self> {
+    /// How often to check on the needs and mood
+    const MOOD_INTERVAL: Duration = Duration::from_secs(30);
+
+    pub fn new(
+        conf: &Config,
+        config_path: PathBuf,
+        achievements: Achievements,
+        unlocks: Unlocks,
+        pet: Pet,
+        meds: Meds,
+        waker: Waker,
+    ) -> Result<Self> {
         let mut stdout = std::io::stdout();
         execute!(
             stdout,
@@ -81,241 +174,1147 @@ impl InterfaceState {
             Clear(ClearType::All)
         )?;
         terminal::enable_raw_mode()?;
-        let temp_icon_path = std::env::temp_dir().join("__kitty_notification_icon.png");
-        std::fs::write(&temp_icon_path, include_bytes!("kitty_icon.png"))?;
         let temp_meow1_path = std::env::temp_dir().join("__meow1.wav");
         std::fs::write(&temp_meow1_path, include_bytes!("sounds/meow1.wav"))?;
         let temp_meow2_path = std::env::temp_dir().join("__meow2.wav");
         std::fs::write(&temp_meow2_path, include_bytes!("sounds/meow2.wav"))?;
+        let remote = Remote::new(waker);
+        let http_api = conf
+            .http_api
+            .as_ref()
+            .map(|api| HttpApi::new(api, remote.handle()))
+            .transpose()?;
+        #[cfg(all(unix, not(target_os = "macos")))]
+        let dbus = if conf.dbus_service {
+            // Not having a session bus (over SSH, etc.) is fine
+            DbusService::new(remote.handle())
+                .inspect_err(|e| warn!("Couldn't start D-Bus service: {e}"))
+                .ok()
+        } else {
+            None
+        };
+        let hooks = Hooks::new(conf.hooks.clone());
+        hooks.run(HookEvent::new(HookKind::Start, "Neutral", 0.5));
+        let picked = Appearance::new(conf);
+        let (appearance, locked) = unlocks.appearance(picked);
         Ok(InterfaceState {
+            screen: Screen::default(),
             lil_guy: LilGuyState::new(
-                conf.character,
+                appearance,
+                pet.stage(),
                 conf.colour,
                 conf.idle_animation_time_min..conf.idle_animation_time_max,
             )?,
             tasks: Tasks::default(),
-            keybinds: BTreeMap::new(),
-            task_timeout: conf.task_timeout_max,
+            keybinds: Keybinds::new(&conf.keys, &conf.tasks_with_meds())
+                .map_err(|_| eyre!("Key conflicts should be caught when loading the config"))?,
+            task_timeout: conf.task_timeout,
             task_timeout_max: conf.task_timeout_max,
             task_animations: VecDeque::new(),
             current_task_animation: None,
+            celebrations: VecDeque::new(),
+            current_celebration: None,
+            achievements,
+            unlocks,
+            pet,
+            meds,
             task_animation_duration: conf.task_animation_duration,
             mood: "".with(style::Color::Grey),
             char_name: conf.character_name().to_string(),
-            temp_icon_path,
+            notifiers: Notifiers::new(&conf.notifiers)?,
+            hooks,
+            remote,
+            http_api,
             #[cfg(all(unix, not(target_os = "macos")))]
-            notifications: Vec::new(),
+            dbus,
+            happiness: 0.5,
+            needs: Needs::default(),
+            mood_policy: conf.mood.build(),
+            snooze_duration: conf.snooze_duration,
+            snoozing: false,
+            doing_early: false,
+            logging_amount: false,
+            amount_input: None,
+            amounts: Vec::new(),
+            show_upcoming: true,
             temp_meow_paths: vec![temp_meow1_path, temp_meow2_path],
-            player: Player::new(None)?,
+            player: Player::new(None)
+                .inspect_err(|e| warn!("No sound, the lil guy will be quiet: {e}"))
+                .ok(),
             text_colour: conf.text_colour,
             task_colour: conf.task_colour,
+            config_error: None,
+            locked_notice: locked,
+            picked,
+            config_path,
+            saved_config: false,
+            settings: None,
+            show_help: false,
+            stats: None,
+            palette: None,
+            guide: None,
+            do_not_disturb: false,
+            next_task_change: None,
         })
     }
-    /// Update the state of the interface, will run every ~100ms
-    /// returns false if the program should exit.
-    pub fn update(&mut self, task_manager: &mut TaskManager) -> Result<bool> {
-        self.keybinds = {
-            let mut number_keybind = 0;
-            self.tasks
-                .current
-                .iter()
-                .chain(self.tasks.past.iter())
-                .map(|task| {
-                    let keybind = task.ty.keybind().unwrap_or_else(|| {
-                        number_keybind += 1;
-                        (b'0' + number_keybind as u8) as char
-                    });
-                    (keybind, task.ty.clone())
-                })
-                .collect()
+    /// Apply a changed config without restarting, here and in the task
+    /// manager. Everything that can fail is set up before anything
+    /// changes, so if it doesn't work out we really are still on the
+    /// old config
+    pub fn reload(&mut self, mut conf: Config, task_manager: &mut TaskManager) -> Result<()> {
+        // Before the task manager takes the tasks
+        let keybinds = Keybinds::new(&conf.keys, &conf.tasks_with_meds())
+            .map_err(|_| eyre!("Key conflicts should be caught when loading the config"))?;
+        let new_tasks = TaskManager::prepare_reload(&mut conf)?;
+        let picked = Appearance::new(&conf);
+        let (appearance, locked) = self.unlocks.appearance(picked);
+        let mut lil_guy = self.lil_guy.clone();
+        lil_guy.reconfigure(
+            appearance,
+            conf.colour,
+            conf.idle_animation_time_min..conf.idle_animation_time_max,
+        )?;
+        let notifiers = if self.notifiers.configs() != conf.notifiers {
+            Some(Notifiers::new(&conf.notifiers)?)
+        } else {
+            None
         };
+        // Last, since the old server has to stop first so the port is
+        // free again, and gets started back up if the new one can't
+        if self.http_api.as_ref().map(HttpApi::config) != conf.http_api.as_ref() {
+            let old = self.http_api.take().map(|api| api.config().clone());
+            match conf
+                .http_api
+                .as_ref()
+                .map(|api| HttpApi::new(api, self.remote.handle()))
+                .transpose()
+            {
+                Ok(api) => self.http_api = api,
+                Err(e) => {
+                    self.http_api =
+                        old.and_then(|api| HttpApi::new(&api, self.remote.handle()).ok());
+                    return Err(e);
+                }
+            }
+        }
+
+        task_manager.reload(new_tasks);
+        self.keybinds = keybinds;
+        self.lil_guy = lil_guy;
+        if let Some(notifiers) = notifiers {
+            self.notifiers.dismiss_all();
+            self.notifiers = notifiers;
+        }
+        self.task_timeout = conf.task_timeout;
+        self.task_timeout_max = conf.task_timeout_max;
+        self.task_animation_duration = conf.task_animation_duration;
+        self.mood_policy = conf.mood.build();
+        self.achievements
+            .reconfigure(&conf.achievements, &conf.medications);
+        self.meds.reconfigure(&conf.medications);
+        self.char_name = conf.character_name().to_string();
+        self.hooks = Hooks::new(conf.hooks.clone());
+        #[cfg(all(unix, not(target_os = "macos")))]
+        if conf.dbus_service != self.dbus.is_some() {
+            self.dbus = None;
+            if conf.dbus_service {
+                self.dbus = DbusService::new(self.remote.handle())
+                    .inspect_err(|e| warn!("Couldn't start D-Bus service: {e}"))
+                    .ok();
+            }
+        }
+        self.snooze_duration = conf.snooze_duration;
+        self.text_colour = conf.text_colour;
+        self.task_colour = conf.task_colour;
+        self.config_error = None;
+        self.locked_notice = locked;
+        self.picked = picked;
+        self.next_task_change = None;
+        Ok(())
+    }
+
+    /// Show that the config couldn't be loaded, we'll keep running on
+    /// the old one
+    pub fn set_config_error(&mut self, error: String) {
+        self.config_error = Some(error);
+    }
+
+    /// Whether the settings screen has saved the config since this was
+    /// last asked, so it doesn't need reloading from the file
+    pub fn take_saved_config(&mut self) -> bool {
+        std::mem::take(&mut self.saved_config)
+    }
+
+    /// Ask to be woken up for whatever needs doing next
+    pub fn schedule(&self, scheduler: &mut Scheduler) {
+        scheduler.wake_at(self.lil_guy.next_update());
+        if let Some((_, end_time)) = &self.current_task_animation {
+            scheduler.wake_at(*end_time);
+        }
+        if let Some((_, _, end_time)) = &self.current_celebration {
+            scheduler.wake_at(*end_time);
+        }
+        if let Some(at) = self.next_task_change {
+            scheduler.wake_at_time(at);
+        }
+        scheduler.wake_at_time(self.pet.next_growth());
+        if let Some(guide) = &self.guide {
+            scheduler.wake_at(guide.next_tick(Instant::now()));
+        }
+        // The countdowns tick every second
+        if self.upcoming_width(self.screen.size()).is_some() && !self.tasks.upcoming.is_empty() {
+            let now = Local::now();
+            scheduler.wake_at_time(
+                now + chrono::Duration::milliseconds(1000 - now.timestamp_subsec_millis() as i64),
+            );
+        }
+        // Needs run down the whole time, and they get sadder the
+        // longer things are left
+        if self.needs.iter().next().is_some() {
+            scheduler.wake_at(Instant::now() + Self::MOOD_INTERVAL);
+        }
+    }
+
+    /// Update the state of the interface after `event` (if there was
+    /// one), or after being woken up for something in `schedule`.
+    /// Returns false if the program should exit.
+    pub fn update(&mut self, event: Option<Event>, task_manager: &mut TaskManager) -> Result<bool> {
         let now = Local::now();
         let now_std = Instant::now();
-        if event::poll(Duration::from_millis(100))? {
-            let ev = event::read()?;
+        let mut actions = Vec::new();
+        // Guides that were followed to the end, these skip straight to
+        // doing the task
+        let mut finished = Vec::new();
+        if let Some(ev) = event {
             match ev {
-                Event::Key(
-                    KeyEvent {
-                        code: KeyCode::Char('q'),
-                        ..
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                }) => actions.push(Action::Quit),
+                Event::Key(key) if self.guide.is_some() => {
+                    let guide = self.guide.as_ref().expect("The guide is open");
+                    match guide.handle_key(key) {
+                        guide::Outcome::Stay => {}
+                        guide::Outcome::Cancel => {
+                            info!("Stopped {} before the end", guide.task().name());
+                            self.guide = None;
+                        }
+                        guide::Outcome::Finish => {
+                            finished.push((guide.task().clone(), guide.amount()));
+                            self.guide = None;
+                        }
+                    }
+                }
+                Event::Key(key) if self.settings.is_some() => {
+                    self.settings_key(key, task_manager)?
+                }
+                Event::Key(key) if self.stats.is_some() => {
+                    let stats = self.stats.as_mut().expect("The stats are open");
+                    match key.code {
+                        // Its own key closes it too
+                        KeyCode::Char(c) if self.keybinds.get(c) == Some(&Binding::Stats) => {
+                            self.stats = None
+                        }
+                        _ => match stats.handle_key(key) {
+                            stats::Outcome::Stay => {}
+                            stats::Outcome::Close => self.stats = None,
+                        },
                     }
-                    | KeyEvent {
-                        code: KeyCode::Char('c'),
-                        modifiers: KeyModifiers::CONTROL,
-                        ..
-                    },
-                ) => {
-                    // Quit
-                    return Ok(false);
+                }
+                Event::Key(key) if self.palette.is_some() => {
+                    let palette = self.palette.as_mut().expect("The palette is open");
+                    match palette.handle_key(key) {
+                        palette::Outcome::Stay => {}
+                        palette::Outcome::Close => self.palette = None,
+                        palette::Outcome::Run(action) => {
+                            self.palette = None;
+                            actions.push(action);
+                        }
+                    }
+                }
+                Event::Key(key) if self.amount_input.is_some() => {
+                    actions.extend(self.amount_key(key));
                 }
                 Event::Key(KeyEvent {
                     code: KeyCode::Char(key),
                     modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
                     ..
-                }) => {
-                    if let Some(task_type) = self.keybinds.remove(&key) {
-                        task_manager.complete_tasks(&task_type, now);
-                        // This would be so much nicer if retain was still drain_filter...
-                        #[cfg(all(unix, not(target_os = "macos")))]
-                        self.notifications
-                            .extract_if(|(ty, _)| ty == &task_type)
-                            .for_each(|(_, n)| n.close());
-                        self.task_animations.push_back(task_type);
+                }) => match self.keybinds.get(key).cloned() {
+                    Some(Binding::Quit) => actions.push(Action::Quit),
+                    Some(Binding::Snooze) => {
+                        self.snoozing = !self.snoozing;
+                        self.doing_early = false;
+                        self.logging_amount = false;
+                    }
+                    Some(Binding::Early) => {
+                        self.doing_early = !self.doing_early;
+                        self.snoozing = false;
+                        self.logging_amount = false;
+                    }
+                    Some(Binding::Amount) => {
+                        self.logging_amount = !self.logging_amount;
+                        self.snoozing = false;
+                        self.doing_early = false;
+                    }
+                    Some(Binding::Upcoming) => actions.push(Action::ToggleUpcoming),
+                    Some(Binding::Settings) => actions.push(Action::OpenSettings),
+                    Some(Binding::Help) => actions.push(Action::ToggleHelp),
+                    Some(Binding::Palette) => {
+                        self.show_help = false;
+                        self.palette = Some(CommandPalette::new(
+                            self.keybinds
+                                .iter()
+                                .filter_map(|(_, binding)| match binding {
+                                    Binding::Task(ty) => Some(ty),
+                                    _ => None,
+                                }),
+                            self.meds.counted().map(|(med, _)| med.name.as_str()),
+                        ));
+                    }
+                    Some(Binding::DoNotDisturb) => actions.push(Action::ToggleDoNotDisturb),
+                    Some(Binding::Stats) => actions.push(Action::ToggleStats),
+                    // Amounts can be logged whenever, a drink is a drink
+                    Some(Binding::Task(task_type)) if self.logging_amount => {
+                        self.logging_amount = false;
+                        if self.amounts.iter().any(|(ty, ..)| *ty == task_type) {
+                            self.amount_input = Some((task_type, String::new()));
+                        }
                     }
+                    Some(Binding::Task(task_type))
+                        if self.doing_early || self.is_due(&task_type) =>
+                    {
+                        actions.push(if self.snoozing {
+                            Action::Snooze(task_type, None)
+                        } else {
+                            Action::Complete(task_type, None)
+                        });
+                        self.snoozing = false;
+                        self.doing_early = false;
+                    }
+                    _ => {}
+                },
+                Event::Key(KeyEvent {
+                    code: KeyCode::Esc, ..
+                }) => {
+                    self.show_help = false;
+                    self.snoozing = false;
+                    self.doing_early = false;
+                    self.logging_amount = false;
+                }
+                Event::Resize(width, height) => {
+                    let room = self.room((width, height));
+                    self.lil_guy.keep_in(room.0);
                 }
                 _ => info!("Unused event: {ev:?}"),
             }
         }
+        // It only counts once the countdown's finished
+        if self
+            .guide
+            .as_ref()
+            .is_some_and(|guide| guide.is_over(now_std))
+        {
+            let guide = self.guide.take().expect("The guide is open");
+            finished.push((guide.task().clone(), guide.amount()));
+        }
+        for (task_type, amount) in finished {
+            self.complete(task_type, amount, task_manager, now)?;
+            self.next_task_change = None;
+        }
+        actions.extend(self.remote.actions());
+        for action in actions {
+            if !self.perform(action, task_manager, now)? {
+                return Ok(false);
+            }
+            self.next_task_change = None;
+        }
+        // Only worth looking once something could have come round again
+        if self.mood_policy.resets_missed() && self.next_task_change.is_none_or(|at| now >= at) {
+            for task_type in task_manager.reset_missed(now)? {
+                info!("Leaving {} behind, it was missed", task_type.name());
+                self.notifiers.dismiss(&task_type);
+                self.next_task_change = None;
+            }
+        }
+        // Working out every schedule is only worth it when something
+        // could have changed
+        if self.next_task_change.is_none_or(|at| now >= at) {
+            self.update_tasks(task_manager, now)?;
+        }
+        if now >= self.pet.next_growth() {
+            let grown = self
+                .pet
+                .grow(task_manager.history(), &task_manager.schedules(), now);
+            if let Some(stage) = grown {
+                info!("Grew up into {}", stage.name());
+                self.lil_guy.set_stage(stage)?;
+                // Age only ever goes up, so nobody grows into being young
+                let message = match stage {
+                    Stage::Young => None,
+                    Stage::Adult => Some(format!("{} is all grown up!", self.char_name)),
+                    Stage::Elder => Some(format!("{} is an elder now, so wise!", self.char_name)),
+                };
+                if let Some(message) = message {
+                    self.celebrate(message)?;
+                }
+            }
+        }
+
+        if let Some((_task_type, end_time)) = &self.current_task_animation {
+            if *end_time < now_std {
+                self.current_task_animation = None;
+            }
+        }
+        if self.current_task_animation.is_none() && !self.task_animations.is_empty() {
+            if let Some(task_animation) = self.task_animations.pop_front() {
+                self.current_task_animation =
+                    Some((task_animation, now_std + self.task_animation_duration));
+            }
+        }
+        if let Some((_, _, end_time)) = &self.current_celebration {
+            if *end_time < now_std {
+                self.current_celebration = None;
+            }
+        }
+        if self.current_celebration.is_none() && self.current_task_animation.is_none() {
+            if let Some((message, animation)) = self.celebrations.pop_front() {
+                self.current_celebration =
+                    Some((message, animation, now_std + self.task_animation_duration));
+            }
+        }
+        self.amounts = task_manager.amounts(now);
+
+        self.needs = Needs::new(
+            self.tasks
+                .past
+                .iter()
+                .chain(&self.tasks.current)
+                .chain(&self.tasks.upcoming)
+                .filter_map(|task| {
+                    let last_done = task_manager.last_done(&task.ty)?;
+                    let level = needs::level(
+                        last_done,
+                        task.when,
+                        now,
+                        self.task_timeout,
+                        self.task_timeout_max,
+                    );
+                    Some((&task.ty, level))
+                }),
+        );
+        let happiness = self.mood_policy.happiness(&self.needs);
+        let old_mood = *self.mood.content();
+        self.happiness = happiness;
+        self.mood = self.mood_policy.mood(happiness);
+        if !old_mood.is_empty() && old_mood != *self.mood.content() {
+            #[cfg(all(unix, not(target_os = "macos")))]
+            if let Some(dbus) = &self.dbus {
+                if let Err(e) = dbus.mood_changed(old_mood, self.mood.content()) {
+                    warn!("Failed to send D-Bus signal: {e}");
+                }
+            }
+            self.hooks.run(HookEvent {
+                previous_mood: Some(old_mood.to_string()),
+                ..self.hook_event(HookKind::MoodChange)
+            });
+        }
+
+        // Every dose wants the same thing
+        let wants: Vec<_> = self
+            .tasks
+            .past
+            .iter()
+            .map(|task| match self.meds.get(&task.ty) {
+                Some(_) => TaskDue {
+                    ty: TaskType::TakeMeds,
+                    when: task.when,
+                },
+                None => task.clone(),
+            })
+            .collect();
+        let screen_size = terminal::size()?;
+        self.lil_guy.update(
+            happiness,
+            self.mood_policy.shows_sadness(),
+            // Doing the task along with you
+            self.guide
+                .as_ref()
+                .map(GuideScreen::task)
+                .or(self.current_task_animation.as_ref().map(|ta| &ta.0)),
+            self.current_celebration.as_ref().map(|(_, anim, _)| anim),
+            self.room(screen_size),
+            &wants,
+        )?;
+        self.remote.set_status(PetStatus {
+            name: self.char_name.clone(),
+            mood: self.mood.content().to_string(),
+            happiness,
+            needs: self.needs.clone(),
+            tasks: self.tasks.clone(),
+        });
+        Ok(true)
+    }
+    /// Work out what's due again, and send notifications for anything
+    /// that just became due
+    fn update_tasks(&mut self, task_manager: &TaskManager, now: DateTime<Local>) -> Result<()> {
         let new_tasks = task_manager.tasks(now)?;
         // This is ugly but uhhh err ummm uhh... Look! Over there! The Good Year blimp!
         let notify_tasks: Vec<_> = new_tasks
             .current
             .iter()
             .filter(|task| !self.tasks.current.contains(task))
-            .map(|task| task.ty.clone())
+            .cloned()
             .collect();
         self.notify_tasks(notify_tasks.into_iter(), false)?;
         let priority_notify_tasks: Vec<_> = new_tasks
             .past
             .iter()
             .filter(|task| !self.tasks.past.contains(task))
-            .map(|task| task.ty.clone())
+            .cloned()
             .collect();
         self.notify_tasks(priority_notify_tasks.into_iter(), true)?;
         self.tasks = new_tasks;
+        self.next_task_change =
+            task_manager.next_change(&self.tasks, self.mood_policy.resets_missed());
+        Ok(())
+    }
 
-        if let Some((_task_type, end_time)) = &self.current_task_animation {
-            if *end_time < now_std {
-                self.current_task_animation = None;
+    /// Actually do a task, once any guide for it is over
+    fn complete(
+        &mut self,
+        task_type: TaskType,
+        amount: Option<u32>,
+        task_manager: &mut TaskManager,
+        now: DateTime<Local>,
+    ) -> Result<()> {
+        let before = task_manager
+            .amounts(now)
+            .into_iter()
+            .find(|(ty, ..)| *ty == task_type);
+        task_manager.complete_tasks(&task_type, now, amount)?;
+        self.notifiers.dismiss(&task_type);
+        self.hooks.run(HookEvent {
+            task: Some(task_type.name().to_string()),
+            ..self.hook_event(HookKind::Complete)
+        });
+        if let Some((_, amount, before)) = before {
+            let after = task_manager
+                .history()
+                .amount_on(&task_type, now.date_naive());
+            if before < amount.target && after >= amount.target {
+                info!("Reached the target for {}", task_type.name());
+                self.celebrate_with(
+                    format!(
+                        "{} had {after} {} today, target reached!",
+                        self.char_name, amount.unit
+                    ),
+                    LilGuyAnimation::Goal,
+                )?;
             }
         }
-        if self.current_task_animation.is_none() && !self.task_animations.is_empty() {
-            if let Some(task_animation) = self.task_animations.pop_front() {
-                self.current_task_animation =
-                    Some((task_animation, now_std + self.task_animation_duration));
+        // Doses count down the pills, and the lil guy is only
+        // relieved once every dose that's due has been taken
+        let animation = if self.meds.get(&task_type).is_some() {
+            if let Some(reminder) = self.meds.take(&task_type) {
+                info!("{reminder}");
+                if !self.do_not_disturb {
+                    self.notifiers.announce(&reminder)?;
+                }
             }
-        }
-
-        let happiness = 1.0
-            - self
-                .tasks
-                .past
-                .iter()
-                .map(|task| {
-                    // This formula is not special its just a random thing I came up with
-                    (((now - task.when).num_seconds() as f32 - self.task_timeout.as_secs_f32())
-                        / self.task_timeout_max.as_secs_f32())
-                    .max(0.0)
-                    .sqrt()
-                })
-                .sum::<f32>()
-                .clamp(0.0, 1.0);
-        self.mood = match happiness {
-            ..=0.1 => "Very Sad".with(style::Color::DarkRed),
-            0.1..=0.4 => "Sad".with(style::Color::DarkMagenta),
-            0.4..=0.6 => "Neutral".with(style::Color::Grey),
-            0.6..=0.9 => "Happy".with(style::Color::Blue),
-            0.9.. => "Very Happy".with(style::Color::Green),
-            _ => "Unknown".with(style::Color::Magenta),
+            let still_due = self
+                .due_tasks()
+                .values()
+                .any(|ty| **ty != task_type && self.meds.get(ty).is_some());
+            (!still_due).then_some(TaskType::TakeMeds)
+        } else {
+            Some(task_type)
         };
+        self.task_animations.extend(animation);
+        let unlocked =
+            self.achievements
+                .check(task_manager.history(), &task_manager.schedules(), now);
+        for achievement in unlocked {
+            info!("Unlocked {}", achievement.name);
+            self.celebrate(format!("Achievement unlocked: {}!", achievement.name))?;
+        }
+        let progress = Progress::new(
+            task_manager.history(),
+            &task_manager.schedules(),
+            &self.achievements,
+            now,
+        );
+        let unlocked = self.unlocks.check(&progress);
+        for item in &unlocked {
+            info!("Unlocked {}", item.name());
+            self.celebrate(format!("Unlocked {}!", item.name()))?;
+        }
+        if !unlocked.is_empty() {
+            // The config might have picked it already
+            let (appearance, locked) = self.unlocks.appearance(self.picked);
+            self.lil_guy.set_appearance(appearance)?;
+            self.locked_notice = locked;
+        }
+        self.amounts = task_manager.amounts(now);
+        Ok(())
+    }
 
-        let screen_size = terminal::size()?;
-        self.lil_guy.update(
-            happiness,
-            self.current_task_animation.as_ref().map(|ta| &ta.0),
-            (
-                0i32..screen_size.0 as i32 - 4,
-                0i32..screen_size.1 as i32 - 12.max(self.keybinds.len() as i32 + 2),
-            ),
-            &self.tasks.past,
-        )?;
+    /// Do something the user asked for, from the keyboard or
+    /// elsewhere, returns false if the program should exit.
+    pub fn perform(
+        &mut self,
+        action: Action,
+        task_manager: &mut TaskManager,
+        now: DateTime<Local>,
+    ) -> Result<bool> {
+        match action {
+            Action::Complete(task_type, amount) => match task_manager.guide(&task_type) {
+                // Already under way, from a key or from outside
+                Some(_) if self.guide.as_ref().is_some_and(|g| *g.task() == task_type) => {
+                    info!("Already following the guide for {}", task_type.name());
+                }
+                Some(guide) => {
+                    if let Some(open) = self.guide.take() {
+                        info!("Stopped {} before the end", open.task().name());
+                    }
+                    self.show_help = false;
+                    self.guide = Some(GuideScreen::new(task_type, amount, guide, Instant::now()));
+                }
+                None => self.complete(task_type, amount, task_manager, now)?,
+            },
+            Action::Snooze(task_type, duration) => {
+                let duration = duration.unwrap_or(self.snooze_duration);
+                // Don't let a silly number from outside end everything
+                let until = chrono::Duration::from_std(duration)
+                    .ok()
+                    .and_then(|duration| now.checked_add_signed(duration));
+                let Some(until) = until else {
+                    warn!("Can't snooze {} for {duration:?}", task_type.name());
+                    return Ok(true);
+                };
+                task_manager.snooze_tasks(&task_type, until);
+                self.notifiers.dismiss(&task_type);
+                self.hooks.run(HookEvent {
+                    task: Some(task_type.name().to_string()),
+                    snoozed_until: Some(until),
+                    ..self.hook_event(HookKind::Snooze)
+                });
+            }
+            Action::Skip(task_type) => {
+                task_manager.skip_tasks(&task_type, now)?;
+                self.notifiers.dismiss(&task_type);
+            }
+            Action::Refill(name) => {
+                info!("Refilled {name}");
+                self.meds.refill(&name);
+            }
+            Action::OpenSettings => {
+                // Edit what's in the file, not what we're running
+                // with, in case it's been changed
+                match Config::from_file(&self.config_path) {
+                    Ok(config) => {
+                        let progress = Progress::new(
+                            task_manager.history(),
+                            &task_manager.schedules(),
+                            &self.achievements,
+                            now,
+                        );
+                        self.settings = Some(SettingsScreen::new(
+                            config,
+                            self.unlocks.locked(),
+                            progress,
+                            self.pet.stage(),
+                        ))
+                    }
+                    Err(e) => self.config_error = Some(e.to_string()),
+                }
+            }
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            Action::ToggleUpcoming => self.show_upcoming = !self.show_upcoming,
+            Action::ToggleDoNotDisturb => self.do_not_disturb = !self.do_not_disturb,
+            Action::ToggleStats => {
+                self.stats = match self.stats {
+                    Some(_) => None,
+                    None => Some(StatsScreen::new(
+                        task_manager.history(),
+                        &task_manager.schedules(),
+                        &self.achievements,
+                        &self.pet,
+                        now,
+                    )),
+                }
+            }
+            Action::Quit => return Ok(false),
+        }
         Ok(true)
     }
+    /// Handle a key press while the settings screen is open
+    fn settings_key(&mut self, key: KeyEvent, task_manager: &mut TaskManager) -> Result<()> {
+        let Some(settings) = &mut self.settings else {
+            return Ok(());
+        };
+        match settings.handle_key(key) {
+            Outcome::Stay => {}
+            Outcome::Close => self.settings = None,
+            Outcome::Save => {
+                if let Err(e) = settings.config().save(&self.config_path) {
+                    settings.set_message(e.to_string());
+                    return Ok(());
+                }
+                let config = self
+                    .settings
+                    .take()
+                    .expect("Settings are open")
+                    .into_config();
+                info!("Saved settings");
+                self.saved_config = true;
+                // It's already saved, so if it won't apply it's the same
+                // as a bad edit to the file
+                if let Err(e) = self.reload(config, task_manager) {
+                    self.set_config_error(e.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
     /// Render the interface
-    pub fn render(&self, writer: &mut impl Write) -> Result<()> {
+    pub fn render(&mut self, writer: &mut impl Write) -> Result<()> {
         let screen_size = terminal::size()?;
-        let text_height = 12.max(self.keybinds.len() as i32 + 2);
-        queue!(writer, Clear(ClearType::All))?;
-        queue!(
-            writer,
-            MoveTo(10, 2),
-            Print(format!("{} is ", self.char_name,).with(self.text_colour)),
-            Print(self.mood),
-            Print(".".with(self.text_colour)),
-        )?;
-        self.lil_guy
-            .render(writer, (2, screen_size.1 as i32 - text_height))?;
-        queue!(
-            writer,
-            MoveTo(3, screen_size.1 - text_height as u16),
-            Print(
-                "=".repeat(screen_size.0 as usize - 6)
-                    .with(self.text_colour)
-            )
-        )?;
-        for (i, (keybind, task_type)) in self.keybinds.iter().enumerate() {
-            queue!(
-                writer,
-                MoveTo(10, i as u16 + screen_size.1 - text_height as u16 + 1),
-                Print(" - ".with(self.text_colour)),
-                Print(task_type.to_string().with(self.task_colour)),
-                Print(" Press '".with(self.text_colour)),
-                Print(keybind.to_string().with(self.task_colour)),
-                Print(format!("' to {}.", task_type.verb()).with(self.text_colour)),
-            )?;
-        }
+        // Taken out while drawing, since drawing needs the rest of us
+        let mut screen = std::mem::take(&mut self.screen);
+        let result = self
+            .draw(screen.canvas(screen_size))
+            .and_then(|_| screen.flush(writer));
+        self.screen = screen;
+        result?;
+        // After the frame's done, so escape sequences and bells don't
+        // end up in the middle of it
+        self.notifiers.write_pending(writer)?;
         writer.flush()?;
         Ok(())
     }
 
+    fn draw(&self, canvas: &mut Canvas) -> Result<()> {
+        let screen_size = canvas.size();
+        if let Some(guide) = &self.guide {
+            return guide.render(
+                canvas,
+                &self.lil_guy,
+                Instant::now(),
+                self.text_colour,
+                self.task_colour,
+            );
+        }
+        if let Some(settings) = &self.settings {
+            settings.render(canvas, self.text_colour, self.task_colour);
+            return Ok(());
+        }
+        if let Some(stats) = &self.stats {
+            stats.render(canvas, self.text_colour, self.task_colour);
+            return Ok(());
+        }
+        match self.layout(screen_size) {
+            Layout::TooSmall => {
+                self.draw_too_small(canvas);
+                return Ok(());
+            }
+            Layout::Compact => self.draw_compact(canvas),
+            Layout::Full { floor } => self.draw_full(canvas, floor)?,
+        }
+        if self.show_help {
+            self.render_help(canvas);
+        }
+        if let Some(palette) = &self.palette {
+            palette.render(canvas, self.text_colour, self.task_colour);
+        }
+        Ok(())
+    }
+
+    /// The lil guy walking around with the tasks under him
+    fn draw_full(&self, canvas: &mut Canvas, floor: u16) -> Result<()> {
+        let screen_size = canvas.size();
+        canvas.move_to(10, 2);
+        self.draw_status(canvas);
+        if let Some(error) = &self.config_error {
+            let message = format!("Config error (still using the old config): {error}");
+            canvas.move_to(0, 0).print(
+                message
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .with(style::Color::Red),
+            );
+        } else if let Some(notice) = &self.locked_notice {
+            canvas
+                .move_to(0, 0)
+                .print(notice.as_str().with(style::Color::DarkYellow));
+        }
+        if let Some(prompt) = self.prompt() {
+            canvas.move_to(10, 3).print(prompt.with(self.text_colour));
+        }
+        if let Some(banner) = self.celebration_banner() {
+            canvas.move_to(10, 1).print(banner);
+        }
+        if let Some(width) = self.upcoming_width(screen_size) {
+            let x = screen_size.0 - width;
+            let height = floor.saturating_sub(3);
+            let mut used = meters::render(canvas, &self.needs, (x, 2), height, self.text_colour);
+            used += meters::render_amounts(
+                canvas,
+                &self.amounts,
+                (x, 2 + used),
+                height - used,
+                self.text_colour,
+            );
+            used += meters::render_meds(
+                canvas,
+                &self.meds,
+                (x, 2 + used),
+                height - used,
+                self.text_colour,
+            );
+            upcoming::render(
+                canvas,
+                &self.tasks.upcoming,
+                &self.keybinds,
+                Local::now(),
+                (x, 2 + used),
+                height - used,
+                self.text_colour,
+            );
+        }
+        self.lil_guy.render(canvas, (2, floor as i32))?;
+        canvas.move_to(3, floor).print(
+            "=".repeat(screen_size.0.saturating_sub(6) as usize)
+                .with(self.text_colour),
+        );
+        for (i, (keybind, task_type)) in self.due_tasks().into_iter().enumerate() {
+            canvas.move_to(10, floor + i as u16 + 1);
+            self.draw_due_task(canvas, keybind, task_type);
+        }
+        canvas.move_to(3, screen_size.1 - 1);
+        self.draw_footer(canvas);
+        Ok(())
+    }
+
+    /// Everything squished into as few lines as possible, with an
+    /// icon instead of the lil guy
+    fn draw_compact(&self, canvas: &mut Canvas) {
+        let screen_size = canvas.size();
+        canvas
+            .move_to(1, 0)
+            .print(self.lil_guy.icon(self.happiness))
+            .print(" ".stylize());
+        self.draw_status(canvas);
+        // Only one line for these, a prompt is more important since
+        // it's waiting on a key
+        if let Some(prompt) = self.prompt() {
+            canvas.move_to(1, 1).print(prompt.with(self.text_colour));
+        } else if let Some(banner) = self.celebration_banner() {
+            canvas.move_to(1, 1).print(banner);
+        } else if self.config_error.is_some() {
+            canvas
+                .move_to(1, 1)
+                .print("Config error, still using the old config".with(style::Color::Red));
+        } else if self.locked_notice.is_some() {
+            canvas
+                .move_to(1, 1)
+                .print("Some picks aren't unlocked yet".with(style::Color::DarkYellow));
+        }
+        let due_tasks = self.due_tasks();
+        let rows = screen_size.1 as usize - 2;
+        // The footer goes if the tasks need its line
+        let show_footer = due_tasks.len() < rows;
+        let shown = if due_tasks.len() > rows {
+            rows - 1
+        } else {
+            due_tasks.len()
+        };
+        for (i, (keybind, task_type)) in due_tasks.iter().take(shown).enumerate() {
+            canvas.move_to(1, i as u16 + 2);
+            self.draw_due_task(canvas, *keybind, task_type);
+        }
+        if shown < due_tasks.len() {
+            canvas
+                .move_to(1, shown as u16 + 2)
+                .print(format!(" ...and {} more", due_tasks.len() - shown).with(self.text_colour));
+        }
+        if show_footer {
+            canvas.move_to(1, screen_size.1 - 1);
+            self.draw_footer(canvas);
+        }
+    }
+
+    fn draw_too_small(&self, canvas: &mut Canvas) {
+        let screen_size = canvas.size();
+        let lines = [
+            "Too small!".to_string(),
+            format!(
+                "I need at least {}x{},",
+                layout::MIN_SIZE.0,
+                layout::MIN_SIZE.1
+            ),
+            format!("this is {}x{}.", screen_size.0, screen_size.1),
+        ];
+        let top = (screen_size.1 / 2).saturating_sub(1);
+        for (i, line) in lines.into_iter().enumerate() {
+            let x = screen_size.0.saturating_sub(line.len() as u16) / 2;
+            canvas
+                .move_to(x, top + i as u16)
+                .print(line.with(self.text_colour));
+        }
+    }
+
+    /// Like "Kitted Catte is Happy.", at the cursor
+    fn draw_status(&self, canvas: &mut Canvas) {
+        canvas
+            .print(format!("{} is ", self.char_name).with(self.text_colour))
+            .print(self.mood)
+            .print(".".with(self.text_colour));
+        if self.do_not_disturb {
+            canvas.print(" (Do not disturb)".with(self.text_colour));
+        }
+    }
+
+    fn celebration_banner(&self) -> Option<StyledContent<String>> {
+        let (message, ..) = self.current_celebration.as_ref()?;
+        Some(message.clone().with(self.task_colour).bold())
+    }
+
+    /// What the next task key will do, if it's not the usual
+    fn prompt(&self) -> Option<String> {
+        if let Some((task_type, input)) = &self.amount_input {
+            let unit = self
+                .amounts
+                .iter()
+                .find(|(ty, ..)| ty == task_type)
+                .map_or("", |(_, amount, _)| &amount.unit);
+            Some(format!(
+                "How many {unit} for {task_type}? {input}_ (Enter to log it)"
+            ))
+        } else if self.snoozing {
+            Some("Press a task's key to snooze it.".to_string())
+        } else if self.doing_early {
+            Some("Press a task's key to do it early.".to_string())
+        } else if self.logging_amount {
+            Some("Press a task's key to log an amount.".to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Handle a key press while an amount is being typed in
+    fn amount_key(&mut self, key: KeyEvent) -> Option<Action> {
+        let (_, input) = self.amount_input.as_mut()?;
+        match key.code {
+            // Six digits is plenty, and always fits
+            KeyCode::Char(c) if c.is_ascii_digit() && input.len() < 6 => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                let (task_type, input) = self.amount_input.take()?;
+                match input.parse() {
+                    Ok(amount) if amount > 0 => {
+                        return Some(Action::Complete(task_type, Some(amount)))
+                    }
+                    _ => {}
+                }
+            }
+            KeyCode::Esc => self.amount_input = None,
+            _ => {}
+        }
+        None
+    }
+
+    fn draw_due_task(&self, canvas: &mut Canvas, keybind: Key, task_type: &TaskType) {
+        let (name, verb) = match self.meds.get(task_type) {
+            Some(med) => (med.label(), TaskType::TakeMeds.verb()),
+            None => (task_type.to_string(), task_type.verb()),
+        };
+        canvas
+            .print(" - ".with(self.text_colour))
+            .print(name.with(self.task_colour));
+        if let Some((_, amount, done)) = self.amounts.iter().find(|(ty, ..)| ty == task_type) {
+            canvas.print(format!(" ({})", amount.describe(*done)).with(self.text_colour));
+        }
+        canvas
+            .print(" Press '".with(self.text_colour))
+            .print(keybind.to_string().with(self.task_colour))
+            .print(format!("' to {verb}.").with(self.text_colour));
+    }
+
+    /// The global keys, at the cursor
+    fn draw_footer(&self, canvas: &mut Canvas) {
+        let footer = self
+            .keybinds
+            .global()
+            .map(|(key, binding)| format!("{key}: {}", binding.name()))
+            .collect::<Vec<_>>()
+            .join("  ");
+        canvas.print(footer.with(self.text_colour));
+    }
+
+    /// Every key and what it does, tasks that aren't due too
+    fn render_help(&self, canvas: &mut Canvas) {
+        let screen_size = canvas.size();
+        let lines: Vec<_> = self
+            .keybinds
+            .iter()
+            .map(|(key, binding)| (key.to_string(), binding.description().to_string()))
+            .chain(self.keybinds.given_way().map(|(binding, usual)| {
+                (
+                    "-".to_string(),
+                    format!(
+                        "{} ('{usual}' is taken, pick a key in the config)",
+                        binding.description()
+                    ),
+                )
+            }))
+            .collect();
+        let width = (lines
+            .iter()
+            .map(|(_, description)| description.len() + 7)
+            .max()
+            .unwrap_or_default() as u16)
+            .min(screen_size.0);
+        let height = (lines.len() as u16 + 4).min(screen_size.1);
+        if width < 10 || height < 5 {
+            return;
+        }
+        let x = (screen_size.0 - width) / 2;
+        let y = (screen_size.1 - height) / 2;
+        draw_box(canvas, (x, y), (width, height), self.text_colour);
+        canvas
+            .move_to(x + 2, y + 1)
+            .print("Keys".with(self.task_colour).bold());
+        for (i, (key, description)) in lines.into_iter().take(height as usize - 4).enumerate() {
+            let description: String = description.chars().take(width as usize - 7).collect();
+            canvas
+                .move_to(x + 2, y + i as u16 + 3)
+                .print(key.with(self.task_colour))
+                .print(format!("  {description}").with(self.text_colour));
+        }
+    }
+
+    /// How much room the upcoming tasks panel needs, if it's shown
+    /// and there's enough room for it
+    fn upcoming_width(&self, screen_size: (u16, u16)) -> Option<u16> {
+        (self.show_upcoming
+            && screen_size.0 >= upcoming::WIDTH * 2
+            && matches!(self.layout(screen_size), Layout::Full { .. }))
+        .then_some(upcoming::WIDTH)
+    }
+
+    fn layout(&self, screen_size: (u16, u16)) -> Layout {
+        Layout::new(screen_size, self.lil_guy.size(), self.due_tasks().len())
+    }
+
+    /// Where the lil guy can walk around. Never smaller than him, so
+    /// he doesn't pace back and forth when it's too small
+    fn room(&self, screen_size: (u16, u16)) -> (Range<i32>, Range<i32>) {
+        let size = self.lil_guy.size();
+        let width = screen_size.0 as i32 - 4 - self.upcoming_width(screen_size).unwrap_or(0) as i32;
+        let floor = match self.layout(screen_size) {
+            Layout::Full { floor } => floor as i32,
+            _ => 0,
+        };
+        (0..width.max(size.0 as i32), 0..floor.max(size.1 as i32))
+    }
+
+    /// Tasks that are due now or overdue, and their keys
+    fn due_tasks(&self) -> BTreeMap<Key, &TaskType> {
+        self.tasks
+            .current
+            .iter()
+            .chain(&self.tasks.past)
+            .filter_map(|task| Some((self.keybinds.task_key(&task.ty)?, &task.ty)))
+            .collect()
+    }
+
+    fn is_due(&self, ty: &TaskType) -> bool {
+        self.due_tasks().values().any(|due| *due == ty)
+    }
+
     /// Send a notification and play a sound for a task
-    // Some things go unused on non-Unix platforms due to missing
-    // features
-    #[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(unused_mut, unused_variables))]
     fn notify_tasks(
         &mut self,
-        tasks: impl Iterator<Item = TaskType>,
+        tasks: impl Iterator<Item = TaskDue>,
         is_priority: bool,
     ) -> Result<()> {
         let mut was_task = false;
 
         for task in tasks {
-            let mut notif = notify_rust::Notification::new()
-                .summary(&format!("{}", task))
-                .appname(NOTIFY_APPNAME)
-                .timeout(Duration::from_secs(60))
-                .icon(&self.temp_icon_path.to_string_lossy())
-                .finalize();
-            #[cfg(all(unix, not(target_os = "macos")))]
-            let notif = notif.hint(Hint::Urgency(if is_priority {
-                Urgency::Critical
-            } else {
-                Urgency::Normal
-            }));
-
-
-            // Notification handle is unused on non-Unix platforms
-            #[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(unused_variables))]
-            let handle = notif.show()?;
-
+            if !self.do_not_disturb {
+                self.notifiers.notify(&task, is_priority)?;
+                was_task = true;
+            }
             #[cfg(all(unix, not(target_os = "macos")))]
-            self.notifications.push((task.clone(), handle));
-
-            was_task = true;
+            if let Some(dbus) = &self.dbus {
+                if let Err(e) = dbus.task_due(&task, is_priority) {
+                    warn!("Failed to send D-Bus signal: {e}");
+                }
+            }
+            self.hooks.run(HookEvent {
+                task: Some(task.ty.name().to_string()),
+                due: Some(task.when),
+                ..self.hook_event(if is_priority {
+                    HookKind::Overdue
+                } else {
+                    HookKind::Due
+                })
+            });
         }
 
-        if was_task {
+        if let (true, Some(player)) = (was_task, &self.player) {
             let song = Song::from_file(
                 self.temp_meow_paths
                     .choose(&mut rand::thread_rng())
                     .unwrap(),
                 None,
             )?;
-            self.player.play_song_next(&song, None)?;
+            player.play_song_next(&song, None)?;
         }
 
         Ok(())
     }
+
+    /// Let everyone know, and queue up a celebration
+    fn celebrate(&mut self, message: String) -> Result<()> {
+        self.celebrate_with(message, LilGuyAnimation::Celebrate)
+    }
+
+    /// [`Self::celebrate`], with a different animation
+    fn celebrate_with(&mut self, message: String, animation: LilGuyAnimation) -> Result<()> {
+        if !self.do_not_disturb {
+            self.notifiers.announce(&message)?;
+        }
+        self.celebrations.push_back((message, animation));
+        Ok(())
+    }
+
+    fn hook_event(&self, kind: HookKind) -> HookEvent {
+        HookEvent::new(kind, self.mood.content(), self.happiness)
+    }
+}
+
+/// Clear a box with a border around it, for things drawn over the
+/// top of the main screen
+fn draw_box(
+    canvas: &mut Canvas,
+    (x, y): (u16, u16),
+    (width, height): (u16, u16),
+    colour: style::Color,
+) {
+    let inside = width.saturating_sub(2) as usize;
+    for row in 0..height {
+        let line = if row == 0 || row == height - 1 {
+            format!("+{}+", "-".repeat(inside))
+        } else {
+            format!("|{}|", " ".repeat(inside))
+        };
+        canvas.move_to(x, y + row).print(line.with(colour));
+    }
 }
 
 impl Drop for InterfaceState {
@@ -323,10 +1322,8 @@ impl Drop for InterfaceState {
     /// notifications.
     fn drop(&mut self) {
         // Try to dismiss all notifications
-        #[cfg(all(unix, not(target_os = "macos")))]
-        for (_, n) in self.notifications.drain(..) {
-            n.close();
-        }
+        self.notifiers.dismiss_all();
+        self.hooks.run_blocking(self.hook_event(HookKind::Exit));
 
         let _ = execute!(
             std::io::stdout(),
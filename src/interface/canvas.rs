@@ -0,0 +1,267 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! Drawing into a grid of cells instead of straight to the terminal,
+//! so only what changed since the last frame has to be sent. Clearing
+//! and redrawing everything made some terminals flicker, and was a lot
+//! of output over SSH.
+
+use std::{fmt::Display, io::Write};
+
+use color_eyre::Result;
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{Attribute, ContentStyle, Print, SetAttribute, SetStyle, StyledContent},
+    terminal::{BeginSynchronizedUpdate, Clear, ClearType, EndSynchronizedUpdate},
+};
+use unicode_width::UnicodeWidthChar;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    ch: char,
+    style: ContentStyle,
+    /// The second half of a wide character (emoji, CJK, etc.) in the
+    /// cell before, so there's nothing to draw here
+    continuation: bool,
+}
+
+impl Cell {
+    fn new(ch: char, style: ContentStyle) -> Self {
+        Cell {
+            ch,
+            style,
+            continuation: false,
+        }
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell::new(' ', ContentStyle::default())
+    }
+}
+
+/// Something to draw a frame on. Works a lot like queueing
+/// `MoveTo`s and `Print`s, anything off the edge gets cut off.
+#[derive(Debug, Default)]
+pub struct Canvas {
+    size: (u16, u16),
+    cells: Vec<Cell>,
+    cursor: (u16, u16),
+}
+
+impl Canvas {
+    fn new(size: (u16, u16)) -> Self {
+        Canvas {
+            size,
+            cells: vec![Cell::default(); size.0 as usize * size.1 as usize],
+            cursor: (0, 0),
+        }
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        self.size
+    }
+
+    fn clear(&mut self) {
+        self.cells.fill(Cell::default());
+        self.cursor = (0, 0);
+    }
+
+    pub fn move_to(&mut self, x: u16, y: u16) -> &mut Self {
+        self.cursor = (x, y);
+        self
+    }
+
+    /// Draw some text at the cursor and move the cursor past it
+    pub fn print<D: Display>(&mut self, content: StyledContent<D>) -> &mut Self {
+        let style = *content.style();
+        for ch in content.content().to_string().chars() {
+            let (x, y) = self.cursor;
+            // Things like combining accents don't get a cell of their
+            // own, and control characters would mess up the terminal
+            let width = match ch.width() {
+                Some(0) | None => continue,
+                Some(width) => width as u16,
+            };
+            if y < self.size.1 {
+                if width == 1 {
+                    self.set(x, y, Cell::new(ch, style));
+                } else if x.saturating_add(1) < self.size.0 {
+                    self.set(x, y, Cell::new(ch, style));
+                    self.set(
+                        x + 1,
+                        y,
+                        Cell {
+                            continuation: true,
+                            ..Cell::new(' ', style)
+                        },
+                    );
+                } else {
+                    // Half of it would be off the edge
+                    self.set(x, y, Cell::new(' ', style));
+                }
+            }
+            self.cursor.0 = x.saturating_add(width);
+        }
+        self
+    }
+
+    /// Put something in a cell, without leaving half of a wide
+    /// character next to it
+    fn set(&mut self, x: u16, y: u16, cell: Cell) {
+        if x >= self.size.0 || y >= self.size.1 {
+            return;
+        }
+        let i = y as usize * self.size.0 as usize + x as usize;
+        let old = self.cells[i];
+        if old.continuation && !cell.continuation && x > 0 {
+            self.cells[i - 1] = Cell::new(' ', self.cells[i - 1].style);
+        }
+        if !old.continuation && x + 1 < self.size.0 && self.cells[i + 1].continuation {
+            self.cells[i + 1] = Cell::new(' ', self.cells[i + 1].style);
+        }
+        self.cells[i] = cell;
+    }
+}
+
+/// The frame on the terminal and the one being drawn, so they can be
+/// compared
+#[derive(Debug, Default)]
+pub struct Screen {
+    front: Canvas,
+    back: Canvas,
+}
+
+impl Screen {
+    /// How big the last frame sent was
+    pub fn size(&self) -> (u16, u16) {
+        self.front.size
+    }
+
+    /// A blank canvas to draw the next frame on
+    pub fn canvas(&mut self, size: (u16, u16)) -> &mut Canvas {
+        if self.back.size != size {
+            self.back = Canvas::new(size);
+        } else {
+            self.back.clear();
+        }
+        &mut self.back
+    }
+
+    /// Send whatever changed since the last frame to the terminal
+    pub fn flush(&mut self, writer: &mut impl Write) -> Result<()> {
+        // Terminals that support it show the whole frame at once
+        // instead of as it comes in
+        queue!(writer, BeginSynchronizedUpdate)?;
+        if self.front.size != self.back.size {
+            // Nothing on the terminal can be trusted after a resize
+            queue!(writer, Clear(ClearType::All))?;
+            self.front = Canvas::new(self.back.size);
+        }
+        let width = self.back.size.0 as usize;
+        // Where the terminal's cursor is and what style it's using,
+        // so they only get sent when they need to change
+        let mut cursor = None;
+        let mut style = None;
+        for (i, (new, old)) in self.back.cells.iter().zip(&self.front.cells).enumerate() {
+            // Continuations are drawn along with the cell before them
+            if new == old || new.continuation {
+                continue;
+            }
+            let position = ((i % width) as u16, (i / width) as u16);
+            if cursor != Some(position) {
+                queue!(writer, MoveTo(position.0, position.1))?;
+            }
+            if style != Some(new.style) {
+                queue!(writer, SetAttribute(Attribute::Reset), SetStyle(new.style))?;
+                style = Some(new.style);
+            }
+            queue!(writer, Print(new.ch))?;
+            let width = new.ch.width().unwrap_or(1) as u16;
+            cursor = Some((position.0 + width, position.1));
+        }
+        queue!(
+            writer,
+            SetAttribute(Attribute::Reset),
+            EndSynchronizedUpdate
+        )?;
+        writer.flush()?;
+        std::mem::swap(&mut self.front, &mut self.back);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Stylize;
+
+    use super::*;
+
+    /// What got printed, without any escape sequences
+    fn printed(output: &[u8]) -> String {
+        let output = String::from_utf8(output.to_vec()).unwrap();
+        let mut text = String::new();
+        let mut chars = output.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                // CSI sequences end with a letter
+                chars.by_ref().find(|c| c.is_ascii_alphabetic());
+            } else {
+                text.push(c);
+            }
+        }
+        text
+    }
+
+    fn frame(screen: &mut Screen, text: &str) -> Vec<u8> {
+        screen.canvas((6, 1)).move_to(0, 0).print(text.stylize());
+        let mut output = Vec::new();
+        screen.flush(&mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn wide_characters_take_two_cells() {
+        let mut canvas = Canvas::new((6, 1));
+        canvas.print("猫a🐱".stylize());
+        let chars: Vec<_> = canvas.cells.iter().map(|cell| cell.ch).collect();
+        assert_eq!(chars, ['猫', ' ', 'a', '🐱', ' ', ' ']);
+        assert!(canvas.cells[1].continuation && canvas.cells[4].continuation);
+        assert_eq!(canvas.cursor, (5, 0));
+
+        // Drawing over half of one doesn't leave the other half behind
+        canvas.move_to(1, 0).print("b".stylize());
+        assert_eq!(canvas.cells[0], Cell::default());
+        canvas.move_to(3, 0).print("c".stylize());
+        assert_eq!(canvas.cells[4], Cell::default());
+
+        // Or put half of one past the edge
+        canvas.move_to(5, 0).print("猫".stylize());
+        assert_eq!(canvas.cells[5], Cell::default());
+    }
+
+    #[test]
+    fn wide_characters_are_drawn_once() {
+        let mut screen = Screen::default();
+        assert_eq!(printed(&frame(&mut screen, "猫猫ab")), "猫猫ab");
+        assert_eq!(printed(&frame(&mut screen, "猫猫ab")), "");
+        assert_eq!(printed(&frame(&mut screen, "a猫猫b")), "a猫猫");
+    }
+}
@@ -0,0 +1,177 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::time::{Duration, Instant};
+
+use color_eyre::Result;
+use crossterm::{
+    event::{KeyCode, KeyEvent},
+    style::{Color, StyledContent, Stylize},
+};
+
+use super::{canvas::Canvas, lil_guy::LilGuyState};
+use crate::task::{Guide, GuideStep, TaskType};
+
+/// How many cells the countdown bar is
+const BAR_WIDTH: usize = 30;
+
+/// What the guide wants done after a key press
+pub enum Outcome {
+    Stay,
+    /// Stopped before the end, so it doesn't count
+    Cancel,
+    /// Ended early with a key, it still counts
+    Finish,
+}
+
+/// A countdown to follow along with, covers up everything else. The
+/// task only gets done once it's over
+pub struct GuideScreen {
+    task: TaskType,
+    /// What to log once it's done, for tasks that keep track of one
+    amount: Option<u32>,
+    guide: Guide,
+    started: Instant,
+}
+
+impl GuideScreen {
+    pub fn new(task: TaskType, amount: Option<u32>, guide: Guide, now: Instant) -> Self {
+        GuideScreen {
+            task,
+            amount,
+            guide,
+            started: now,
+        }
+    }
+
+    pub fn task(&self) -> &TaskType {
+        &self.task
+    }
+
+    pub fn amount(&self) -> Option<u32> {
+        self.amount
+    }
+
+    pub fn is_over(&self, now: Instant) -> bool {
+        now >= self.started + self.guide.duration
+    }
+
+    /// When the countdown next ticks over
+    pub fn next_tick(&self, now: Instant) -> Instant {
+        let elapsed = now.saturating_duration_since(self.started);
+        let tick = self.started + Duration::from_secs(elapsed.as_secs() + 1);
+        tick.min(self.started + self.guide.duration)
+    }
+
+    pub fn handle_key(&self, key: KeyEvent) -> Outcome {
+        match key.code {
+            KeyCode::Esc => Outcome::Cancel,
+            KeyCode::Enter | KeyCode::Char(' ') if self.guide.finish_early => Outcome::Finish,
+            _ => Outcome::Stay,
+        }
+    }
+
+    /// Which step it's on, going round again after the last one, and
+    /// how long is left of it
+    fn step(&self, elapsed: Duration) -> Option<(&GuideStep, Duration)> {
+        let round: Duration = self.guide.steps.iter().map(|step| step.duration).sum();
+        // Zero length steps get caught when loading the config, but
+        // going round forever would be bad
+        if round.is_zero() {
+            return None;
+        }
+        let mut into = Duration::from_nanos((elapsed.as_nanos() % round.as_nanos()) as u64);
+        for step in &self.guide.steps {
+            if into < step.duration {
+                return Some((step, step.duration - into));
+            }
+            into -= step.duration;
+        }
+        None
+    }
+
+    pub fn render(
+        &self,
+        canvas: &mut Canvas,
+        lil_guy: &LilGuyState,
+        now: Instant,
+        text_colour: Color,
+        highlight_colour: Color,
+    ) -> Result<()> {
+        let (width, height) = canvas.size();
+        let elapsed = now.saturating_duration_since(self.started);
+        let left = self.guide.duration.saturating_sub(elapsed);
+        centred(
+            canvas,
+            1,
+            self.task.name().to_string().with(highlight_colour).bold(),
+        );
+        centred(canvas, 2, self.guide.text.clone().with(text_colour));
+
+        // Him in the middle, with everything else under him
+        let size = lil_guy.size();
+        let floor = (4 + size.1).min(height.saturating_sub(5));
+        lil_guy.render_at(
+            canvas,
+            ((width.saturating_sub(size.0) / 2) as i32, floor as i32),
+        )?;
+        if let Some((step, step_left)) = self.step(elapsed) {
+            centred(
+                canvas,
+                floor + 1,
+                format!("{} ({})", step.text, whole_seconds(step_left))
+                    .with(highlight_colour)
+                    .bold(),
+            );
+        }
+        let done = elapsed.as_secs_f32() / self.guide.duration.as_secs_f32();
+        let filled = ((done * BAR_WIDTH as f32).round() as usize).min(BAR_WIDTH);
+        let bar = format!(
+            "{}{} {}s",
+            "█".repeat(filled),
+            "░".repeat(BAR_WIDTH - filled),
+            whole_seconds(left)
+        );
+        centred(canvas, floor + 2, bar.with(text_colour));
+        let help = if self.guide.finish_early {
+            "Esc: stop (won't count)  Enter: finish early"
+        } else {
+            "Esc: stop (won't count)"
+        };
+        centred(
+            canvas,
+            height.saturating_sub(1),
+            help.to_string().with(text_colour),
+        );
+        Ok(())
+    }
+}
+
+/// Rounded up, so it counts down from 20 and not 19
+fn whole_seconds(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+fn centred(canvas: &mut Canvas, y: u16, text: StyledContent<String>) {
+    let x = canvas
+        .size()
+        .0
+        .saturating_sub(text.content().chars().count() as u16)
+        / 2;
+    canvas.move_to(x, y).print(text);
+}
@@ -0,0 +1,62 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+/// Smaller than this and there's no point trying
+pub const MIN_SIZE: (u16, u16) = (30, 6);
+
+/// Rows at the top for the name, mood and prompts
+const HEADER_HEIGHT: u16 = 4;
+
+/// How much room there usually is under the floor, it's shrunk on
+/// shorter terminals before giving up on the room entirely
+const USUAL_BELOW_FLOOR: u16 = 12;
+
+/// Where things go on the main screen, depending on how much room
+/// there is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Just a message asking for a bigger terminal
+    TooSmall,
+    /// No room for the lil guy to walk around (like in a tmux split),
+    /// he's a little icon next to his name instead
+    Compact,
+    /// The lil guy walking around on the `=` line at row `floor`,
+    /// with the tasks under it
+    Full { floor: u16 },
+}
+
+impl Layout {
+    pub fn new(screen_size: (u16, u16), lil_guy_size: (u16, u16), due_tasks: usize) -> Self {
+        let (width, height) = screen_size;
+        if width < MIN_SIZE.0 || height < MIN_SIZE.1 {
+            return Layout::TooSmall;
+        }
+        // The due tasks, a gap and the footer
+        let below_floor = due_tasks as u16 + 2;
+        let room_height = HEADER_HEIGHT + lil_guy_size.1;
+        if height < room_height + below_floor || width < lil_guy_size.0 + 6 {
+            return Layout::Compact;
+        }
+        let below_floor = (height - room_height)
+            .min(USUAL_BELOW_FLOOR)
+            .max(below_floor);
+        Layout::Full {
+            floor: height - below_floor,
+        }
+    }
+}
@@ -18,7 +18,6 @@ along with Tuigotchi Health. If not, see
 
 use std::{
     collections::HashMap,
-    io::Write,
     ops::Range,
     str::FromStr,
     time::{Duration, Instant},
@@ -28,18 +27,22 @@ use color_eyre::{
     eyre::{bail, OptionExt},
     Result,
 };
-use crossterm::{
-    cursor::MoveTo,
-    queue,
-    style::{self, Print},
-};
+use crossterm::style::{self, Color, StyledContent, Stylize};
 use rand::{thread_rng, Rng};
 
+use super::canvas::Canvas;
 use crate::task_manager::TaskDue;
-use crate::{config::CharacterChoice, task::TaskType};
+use crate::{
+    config::CharacterChoice,
+    pet::Stage,
+    task::TaskType,
+    unlockables::{Appearance, Unlockable},
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LilGuyState {
+    appearance: Appearance,
+    stage: Stage,
     animations: Animations,
     colour: style::Color,
     current_animation: LilGuyAnimation,
@@ -50,7 +53,7 @@ pub struct LilGuyState {
     pos: (i32, i32),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Animations {
     anims: HashMap<LilGuyAnimation, Vec<AnimationFrame>>,
     max_sadness: u32,
@@ -58,32 +61,24 @@ struct Animations {
 }
 
 impl Animations {
-    fn load(text: &str) -> Result<Animations> {
-        let anims: HashMap<_, _> = text
+    /// Animations before any `stage` line are for every stage, the
+    /// ones in a stage's section replace them
+    fn load(text: &str, stage: Stage) -> Result<Animations> {
+        let mut anims = HashMap::new();
+        for section in text
             .lines()
             .collect::<Vec<_>>()
-            .chunk_by(|_a, b| !b.starts_with("animation "))
-            .map(|animation_lines| {
-                let animation_name: LilGuyAnimation = animation_lines[0]
-                    .trim_start_matches("animation ")
-                    .parse()?;
-                let animation_frames: Vec<_> = animation_lines[1..]
-                    .chunk_by(|_a, b| !b.starts_with("frame "))
-                    .map(|frame_lines| {
-                        let frame_time = frame_lines[0]
-                            .trim_start_matches("frame ")
-                            .trim_end_matches("ms");
-                        let frame_time: f64 = frame_time.parse()?;
-                        let frame_time = std::time::Duration::from_secs_f64(frame_time / 1000.0);
-                        Ok(AnimationFrame {
-                            duration: frame_time,
-                            lines: frame_lines[1..].iter().map(|s| s.to_string()).collect(),
-                        })
-                    })
-                    .collect::<Result<_>>()?;
-                Ok((animation_name, animation_frames))
-            })
-            .collect::<Result<_>>()?;
+            .chunk_by(|_a, b| !b.starts_with("stage "))
+        {
+            match section[0].strip_prefix("stage ") {
+                None => anims.extend(Self::parse(section)?),
+                Some(name) if !Stage::ALL.iter().any(|s| s.name() == name) => {
+                    bail!("Unknown stage: {name}")
+                }
+                Some(name) if name == stage.name() => anims.extend(Self::parse(&section[1..])?),
+                Some(_) => {}
+            }
+        }
         Ok(Animations {
             max_sadness: anims
                 .keys()
@@ -114,6 +109,31 @@ impl Animations {
             anims,
         })
     }
+    fn parse(lines: &[&str]) -> Result<HashMap<LilGuyAnimation, Vec<AnimationFrame>>> {
+        lines
+            .chunk_by(|_a, b| !b.starts_with("animation "))
+            .map(|animation_lines| {
+                let animation_name: LilGuyAnimation = animation_lines[0]
+                    .trim_start_matches("animation ")
+                    .parse()?;
+                let animation_frames: Vec<_> = animation_lines[1..]
+                    .chunk_by(|_a, b| !b.starts_with("frame "))
+                    .map(|frame_lines| {
+                        let frame_time = frame_lines[0]
+                            .trim_start_matches("frame ")
+                            .trim_end_matches("ms");
+                        let frame_time: f64 = frame_time.parse()?;
+                        let frame_time = std::time::Duration::from_secs_f64(frame_time / 1000.0);
+                        Ok(AnimationFrame {
+                            duration: frame_time,
+                            lines: frame_lines[1..].iter().map(|s| s.to_string()).collect(),
+                        })
+                    })
+                    .collect::<Result<_>>()?;
+                Ok((animation_name, animation_frames))
+            })
+            .collect()
+    }
     fn get(&self, anim: &LilGuyAnimation) -> Result<&[AnimationFrame]> {
         self.anims
             .get(anim)
@@ -126,7 +146,7 @@ impl Animations {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct AnimationFrame {
     duration: Duration,
     lines: Vec<String>,
@@ -142,6 +162,12 @@ pub enum LilGuyAnimation {
     Sad(u32),
     Want(TaskType),
     Task(TaskType),
+    /// Something got unlocked!
+    Celebrate,
+    /// Instead of being sad, with the gentle mood
+    Sleepy,
+    /// Had enough of something for the day, like water
+    Goal,
 }
 
 impl LilGuyAnimation {
@@ -156,6 +182,8 @@ impl LilGuyAnimation {
             LilGuyAnimation::Task(t) if *t != TaskType::Other("".to_string()) => {
                 LilGuyAnimation::Task(TaskType::Other("".to_string()))
             }
+            LilGuyAnimation::Sleepy => LilGuyAnimation::Task(TaskType::Sleep),
+            LilGuyAnimation::Goal => LilGuyAnimation::Celebrate,
             LilGuyAnimation::Idle => bail!("No fallback animation for idle!"),
             _ => LilGuyAnimation::Idle,
         })
@@ -181,6 +209,9 @@ impl FromStr for LilGuyAnimation {
             "want/take_meds" => Self::Want(TaskType::TakeMeds),
             "want/sleep" => Self::Want(TaskType::Sleep),
             "want/bathroom" => Self::Want(TaskType::Bathroom),
+            "celebrate" => Self::Celebrate,
+            "sleepy" => Self::Sleepy,
+            "goal" => Self::Goal,
             "task/general" => Self::Task(TaskType::Other(String::new())),
             "task/eat" => Self::Task(TaskType::Eat),
             "task/drink" => Self::Task(TaskType::Drink),
@@ -197,12 +228,15 @@ impl FromStr for LilGuyAnimation {
 
 impl LilGuyState {
     pub fn new(
-        character: CharacterChoice,
+        appearance: Appearance,
+        stage: Stage,
         colour: style::Color,
         idle_animation_time: Range<Duration>,
     ) -> Result<Self> {
         Ok(LilGuyState {
-            animations: Animations::load(character.animation_file())?,
+            appearance,
+            stage,
+            animations: Animations::load(appearance.character.animation_file(), stage)?,
             colour,
             current_animation: LilGuyAnimation::Idle,
             animation_frame: 0,
@@ -212,10 +246,48 @@ impl LilGuyState {
             pos: (0, 0),
         })
     }
+    /// Apply new settings, switching characters if needed
+    pub fn reconfigure(
+        &mut self,
+        appearance: Appearance,
+        colour: style::Color,
+        idle_animation_time: Range<Duration>,
+    ) -> Result<()> {
+        self.set_appearance(appearance)?;
+        self.colour = colour;
+        self.idle_animation_time = idle_animation_time;
+        Ok(())
+    }
+    /// Switch how they look, and which character they are if needed
+    pub fn set_appearance(&mut self, appearance: Appearance) -> Result<()> {
+        let changed = appearance.character != self.appearance.character;
+        self.appearance = appearance;
+        if changed {
+            self.reload()?;
+        }
+        Ok(())
+    }
+    /// They grew up!
+    pub fn set_stage(&mut self, stage: Stage) -> Result<()> {
+        if stage != self.stage {
+            self.stage = stage;
+            self.reload()?;
+        }
+        Ok(())
+    }
+    fn reload(&mut self) -> Result<()> {
+        self.animations = Animations::load(self.appearance.character.animation_file(), self.stage)?;
+        self.current_animation = LilGuyAnimation::Idle;
+        self.animation_frame = 0;
+        self.next_frame_time = Instant::now();
+        Ok(())
+    }
     pub fn update(
         &mut self,
         happiness: f32,
+        shows_sadness: bool,
         ongoing_task: Option<&TaskType>,
+        celebrating: Option<&LilGuyAnimation>,
         room_bounds: (Range<i32>, Range<i32>),
         wants: &[TaskDue],
     ) -> Result<()> {
@@ -224,6 +296,8 @@ impl LilGuyState {
             Some(LilGuyAnimation::WalkRight)
         } else if self.pos.0 + self.animations.max_bounds.0 as i32 > room_bounds.0.end {
             Some(LilGuyAnimation::WalkLeft)
+        } else if let Some(anim) = celebrating {
+            Some(anim.clone())
         } else if let Some(task) = ongoing_task {
             Some(LilGuyAnimation::Task(task.clone()))
         } else if happiness < 0.6 {
@@ -238,10 +312,12 @@ impl LilGuyState {
             });
             if let Some(ty) = anim {
                 Some(LilGuyAnimation::Want(ty))
-            } else {
+            } else if shows_sadness {
                 Some(LilGuyAnimation::Sad(sad_level))
+            } else {
+                Some(LilGuyAnimation::Sleepy)
             }
-        } else if self.idle_animation_change < Instant::now() {
+        } else if self.idle_animation_change <= now {
             let mut rng = thread_rng();
             self.idle_animation_change =
                 Instant::now() + rng.gen_range(self.idle_animation_time.clone());
@@ -257,23 +333,28 @@ impl LilGuyState {
             } else {
                 None
             }
-        } else if ongoing_task.is_none()
-            && matches!(self.current_animation, LilGuyAnimation::Task(_))
+        } else if (ongoing_task.is_none()
+            && matches!(self.current_animation, LilGuyAnimation::Task(_)))
+            || (matches!(
+                self.current_animation,
+                LilGuyAnimation::Celebrate | LilGuyAnimation::Goal
+            ) && celebrating != Some(&self.current_animation))
         {
             Some(LilGuyAnimation::Idle)
         } else {
             None
         };
         if let Some(new_animation) = new_animation {
+            // Reset the animation frame if the animation switches
             if self.current_animation != new_animation {
                 self.current_animation = new_animation;
                 self.animation_frame = 0;
-                self.next_frame_time = now;
+                self.next_frame_time =
+                    now + self.animations.get(&self.current_animation)?[0].duration;
             }
         }
         let anim = &self.animations.get(&self.current_animation)?;
-        // Reset the animation frame if the animation switches
-        if now > self.next_frame_time {
+        if now >= self.next_frame_time {
             self.animation_frame += 1;
             if self.animation_frame >= anim.len() {
                 self.animation_frame = 0;
@@ -287,28 +368,132 @@ impl LilGuyState {
         }
         Ok(())
     }
-    pub fn render(&self, writer: &mut impl Write, center: (i32, i32)) -> Result<()> {
-        let pos = (center.0 + self.pos.0, center.1 + self.pos.1);
-        let frame = &self.animations.get(&self.current_animation)?[self.animation_frame];
-        let y_offset = -(frame.lines.len() as i32);
-        queue!(
-            writer,
-            style::SetColors(style::Colors {
-                foreground: Some(self.colour),
-                background: None
-            })
-        )?;
-        for (y, line) in frame.lines.iter().enumerate() {
-            queue!(
-                writer,
-                MoveTo(
-                    pos.0.clamp(0, 65535) as u16,
-                    (pos.1 + y as i32 + y_offset).clamp(0, 65535) as u16
-                ),
-                Print(line),
-            )?;
+    /// The most room any of his frames take up, with his hat on
+    pub fn size(&self) -> (u16, u16) {
+        let hat = self
+            .appearance
+            .accessory
+            .map_or(0, |accessory| accessory.lines().len());
+        (
+            self.animations.max_bounds.0 as u16,
+            (self.animations.max_bounds.1 as usize + hat) as u16,
+        )
+    }
+    /// Put him back in the room if it got smaller, instead of him
+    /// walking back in from off screen
+    pub fn keep_in(&mut self, room: Range<i32>) {
+        let right = (room.end - self.animations.max_bounds.0 as i32).max(room.start);
+        self.pos.0 = self.pos.0.clamp(room.start, right);
+    }
+    /// A tiny version of him for when there's no room for the real one
+    pub fn icon(&self, happiness: f32) -> StyledContent<&'static str> {
+        let faces = match self.appearance.character {
+            CharacterChoice::DebugGuy => ["(;-;)", "(.v.)", "\\(^v^)/"],
+            CharacterChoice::Kitty => ["=;.;=", "=^.^=", "=^w^="],
+            CharacterChoice::Puppy => ["U;x;U", "U.x.U", "U^x^U"],
+            CharacterChoice::Lizard => ["<;_;>", "<._.>", "<^_^>"],
+            CharacterChoice::AutismCreature => ["(.~.)", "(ovo)", "(^w^)"],
+        };
+        let face = match happiness {
+            ..0.4 => faces[0],
+            0.4..0.6 => faces[1],
+            _ => faces[2],
+        };
+        face.with(
+            self.appearance
+                .theme
+                .map_or(self.colour, |theme| theme.colour(0, 1)),
+        )
+    }
+    /// When `update` will next have something to do
+    pub fn next_update(&self) -> Instant {
+        // Only counts if it hasn't passed yet, otherwise it's waiting
+        // on something else (like cheering up) that'll wake us anyways
+        if self.idle_animation_change > Instant::now() {
+            self.next_frame_time.min(self.idle_animation_change)
+        } else {
+            self.next_frame_time
         }
-        queue!(writer, style::ResetColor)?;
+    }
+    pub fn render(&self, canvas: &mut Canvas, center: (i32, i32)) -> Result<()> {
+        self.render_at(canvas, (center.0 + self.pos.0, center.1 + self.pos.1))
+    }
+    /// Draw him standing on `pos`, wherever he's walked off to, for
+    /// when he's shown on his own
+    pub fn render_at(&self, canvas: &mut Canvas, pos: (i32, i32)) -> Result<()> {
+        let frame = &self.animations.get(&self.current_animation)?[self.animation_frame];
+        draw_frame(canvas, pos, &frame.lines, self.appearance, self.colour, &[]);
         Ok(())
     }
 }
+
+/// The first idle frame, for showing off what a character looks like
+pub fn preview(character: CharacterChoice, stage: Stage) -> Result<Vec<String>> {
+    let animations = Animations::load(character.animation_file(), stage)?;
+    Ok(animations.get(&LilGuyAnimation::Idle)?[0].lines.clone())
+}
+
+/// Draw a frame with his accessory on, standing on `pos`. Anything in
+/// `locked` is drawn as a silhouette
+pub fn draw_frame(
+    canvas: &mut Canvas,
+    pos: (i32, i32),
+    lines: &[String],
+    appearance: Appearance,
+    colour: Color,
+    locked: &[Unlockable],
+) {
+    fn silhouette(line: &str) -> String {
+        line.chars()
+            .map(|c| if c == ' ' { ' ' } else { '█' })
+            .collect()
+    }
+    let mut print = |x: i32, y: i32, line: String, colour: Color| {
+        canvas
+            .move_to(x.clamp(0, 65535) as u16, y.clamp(0, 65535) as u16)
+            .print(line.with(colour));
+    };
+    let top = pos.1 - lines.len() as i32;
+    let character_locked = locked.contains(&Unlockable::Character(appearance.character));
+    let theme_locked = appearance
+        .theme
+        .is_some_and(|theme| locked.contains(&Unlockable::Theme(theme)));
+    for (y, line) in lines.iter().enumerate() {
+        let line = if character_locked || theme_locked {
+            silhouette(line)
+        } else {
+            line.clone()
+        };
+        let colour = match appearance.theme {
+            _ if character_locked => Color::DarkGrey,
+            Some(theme) => theme.colour(y, lines.len()),
+            None => colour,
+        };
+        print(pos.0, top + y as i32, line, colour);
+    }
+    if let Some(accessory) = appearance.accessory {
+        let hat = accessory.lines();
+        let accessory_locked = locked.contains(&Unlockable::Accessory(accessory));
+        for (y, line) in hat.iter().enumerate() {
+            let width = hat.iter().map(|line| line.len()).max().unwrap_or(0) as i32;
+            let x = pos.0 + head_x(appearance.character) - width / 2;
+            let y = top - hat.len() as i32 + y as i32;
+            if accessory_locked {
+                print(x, y, silhouette(line), Color::DarkGrey);
+            } else {
+                print(x, y, line.to_string(), accessory.colour());
+            }
+        }
+    }
+}
+
+/// Where the middle of his head is in his frames, to put hats on
+fn head_x(character: CharacterChoice) -> i32 {
+    match character {
+        CharacterChoice::DebugGuy => 2,
+        CharacterChoice::Kitty => 6,
+        CharacterChoice::Puppy => 9,
+        CharacterChoice::Lizard => 16,
+        CharacterChoice::AutismCreature => 6,
+    }
+}
@@ -0,0 +1,124 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use crossterm::style::{Color, Stylize};
+
+use super::canvas::Canvas;
+use crate::{
+    meds::Meds,
+    needs::{Needs, DUE_LEVEL},
+    task::{Amount, TaskType},
+};
+
+/// How many cells the bars are
+const BAR_WIDTH: usize = 12;
+
+/// A bar for each need, at the top of the side panel. Returns how many
+/// lines it took up, with a gap after
+pub fn render(
+    canvas: &mut Canvas,
+    needs: &Needs,
+    (x, y): (u16, u16),
+    height: u16,
+    text_colour: Color,
+) -> u16 {
+    let count = needs.iter().count() as u16;
+    // Leave some room for what's coming up
+    if count == 0 || height < count + 4 {
+        return 0;
+    }
+    canvas.move_to(x, y).print("Needs".with(text_colour).bold());
+    for (i, (need, level)) in needs.iter().enumerate() {
+        let filled = (level * BAR_WIDTH as f32).round() as usize;
+        let colour = match level {
+            l if l >= DUE_LEVEL => Color::Green,
+            l if l >= DUE_LEVEL / 2.0 => Color::Yellow,
+            _ => Color::Red,
+        };
+        canvas
+            .move_to(x, y + i as u16 + 1)
+            .print(format!("{:<8} ", need.name()).with(text_colour))
+            .print("█".repeat(filled).with(colour))
+            .print("░".repeat(BAR_WIDTH - filled).with(Color::DarkGrey))
+            .print(format!(" {:>3.0}%", level * 100.0).with(text_colour));
+    }
+    count + 2
+}
+
+/// How far along each amount is today, under the needs. Returns how
+/// many lines it took up, with a gap after
+pub fn render_amounts(
+    canvas: &mut Canvas,
+    amounts: &[(TaskType, Amount, u32)],
+    (x, y): (u16, u16),
+    height: u16,
+    text_colour: Color,
+) -> u16 {
+    // The name and how much on one line, the bar under it
+    let lines = amounts.len() as u16 * 2;
+    if lines == 0 || height < lines + 4 {
+        return 0;
+    }
+    canvas.move_to(x, y).print("Today".with(text_colour).bold());
+    for (i, (ty, amount, done)) in amounts.iter().enumerate() {
+        let progress = (*done as f32 / amount.target as f32).min(1.0);
+        let filled = (progress * BAR_WIDTH as f32).round() as usize;
+        let colour = if *done >= amount.target {
+            Color::Green
+        } else {
+            Color::Blue
+        };
+        let row = y + i as u16 * 2 + 1;
+        canvas
+            .move_to(x, row)
+            .print(format!("{} {}", ty.name(), amount.describe(*done)).with(text_colour));
+        canvas
+            .move_to(x, row + 1)
+            .print("█".repeat(filled).with(colour))
+            .print("░".repeat(BAR_WIDTH - filled).with(Color::DarkGrey))
+            .print(format!(" {:>3.0}%", progress * 100.0).with(text_colour));
+    }
+    lines + 2
+}
+
+/// How many pills are left of the medications that are counted, in
+/// red when it's time to refill. Returns how many lines it took up,
+/// with a gap after
+pub fn render_meds(
+    canvas: &mut Canvas,
+    meds: &Meds,
+    (x, y): (u16, u16),
+    height: u16,
+    text_colour: Color,
+) -> u16 {
+    let count = meds.counted().count() as u16;
+    if count == 0 || height < count + 4 {
+        return 0;
+    }
+    canvas.move_to(x, y).print("Meds".with(text_colour).bold());
+    for (i, (med, pills)) in meds.counted().enumerate() {
+        let line = format!("{:<14.14} {pills:>4} left", med.name);
+        let line = if meds.is_low(med) {
+            format!("{line} refill").with(Color::Red)
+        } else {
+            line.with(text_colour)
+        };
+        canvas.move_to(x, y + i as u16 + 1).print(line);
+    }
+    count + 2
+}
@@ -0,0 +1,197 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use crossterm::{
+    event::{KeyCode, KeyEvent},
+    style::{Color, Stylize},
+};
+
+use super::{canvas::Canvas, draw_box};
+use crate::{action::Action, task::TaskType};
+
+/// How many matches to show at once
+const SHOWN: usize = 8;
+
+/// What the palette wants done after a key press
+pub enum Outcome {
+    Stay,
+    Close,
+    Run(Action),
+}
+
+struct Command {
+    name: String,
+    action: Action,
+}
+
+/// Type part of a command's name to find it, for doing things that
+/// don't have a key, or for tasks that aren't due yet
+pub struct CommandPalette {
+    query: String,
+    selected: usize,
+    commands: Vec<Command>,
+}
+
+impl CommandPalette {
+    pub fn new<'a>(
+        tasks: impl Iterator<Item = &'a TaskType>,
+        refills: impl Iterator<Item = &'a str>,
+    ) -> Self {
+        let mut commands = Vec::new();
+        for ty in tasks {
+            let name = ty.name();
+            commands.extend([
+                Command {
+                    name: format!("done {name}"),
+                    action: Action::Complete(ty.clone(), None),
+                },
+                Command {
+                    name: format!("snooze {name}"),
+                    action: Action::Snooze(ty.clone(), None),
+                },
+                Command {
+                    name: format!("skip {name}"),
+                    action: Action::Skip(ty.clone()),
+                },
+            ]);
+        }
+        commands.extend(refills.map(|name| Command {
+            name: format!("refill {name}"),
+            action: Action::Refill(name.to_string()),
+        }));
+        commands.extend(
+            [
+                ("settings", Action::OpenSettings),
+                ("help", Action::ToggleHelp),
+                ("upcoming tasks", Action::ToggleUpcoming),
+                ("do not disturb", Action::ToggleDoNotDisturb),
+                ("stats", Action::ToggleStats),
+                ("quit", Action::Quit),
+            ]
+            .map(|(name, action)| Command {
+                name: name.to_string(),
+                action,
+            }),
+        );
+        CommandPalette {
+            query: String::new(),
+            selected: 0,
+            commands,
+        }
+    }
+
+    /// Commands that match what's been typed, best first
+    fn matches(&self) -> Vec<&Command> {
+        let mut matches: Vec<_> = self
+            .commands
+            .iter()
+            .filter_map(|command| Some((fuzzy_score(&self.query, &command.name)?, command)))
+            .collect();
+        // Stable, so equally good matches stay in order
+        matches.sort_by_key(|(score, _)| -score);
+        matches.into_iter().map(|(_, command)| command).collect()
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Outcome {
+        match key.code {
+            KeyCode::Esc => return Outcome::Close,
+            KeyCode::Enter => {
+                return match self.matches().get(self.selected) {
+                    Some(command) => Outcome::Run(command.action.clone()),
+                    None => Outcome::Stay,
+                }
+            }
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Tab => {
+                self.selected = (self.selected + 1).min(self.matches().len().saturating_sub(1))
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.selected = 0;
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.selected = 0;
+            }
+            _ => {}
+        }
+        Outcome::Stay
+    }
+
+    pub fn render(&self, canvas: &mut Canvas, text_colour: Color, highlight_colour: Color) {
+        let screen_size = canvas.size();
+        let matches = self.matches();
+        let width = 50.min(screen_size.0.saturating_sub(4));
+        if width < 10 {
+            return;
+        }
+        let shown = SHOWN.min(screen_size.1.saturating_sub(7) as usize);
+        // Keep the selected one on screen
+        let scroll = (self.selected + 1).saturating_sub(shown);
+        let height = 3 + matches.len().min(shown) as u16;
+        let x = (screen_size.0 - width) / 2;
+        draw_box(canvas, (x, 2), (width, height), text_colour);
+        let inside = width as usize - 4;
+        // Show the end of the query if it's too long
+        let query = format!(":{}_", self.query);
+        let query: String = query
+            .chars()
+            .skip(query.chars().count().saturating_sub(inside))
+            .collect();
+        canvas.move_to(x + 2, 3).print(query.with(highlight_colour));
+        for (y, (i, command)) in matches
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(shown)
+            .enumerate()
+        {
+            let name: String = command.name.chars().take(inside).collect();
+            canvas
+                .move_to(x + 2, y as u16 + 4)
+                .print(if i == self.selected {
+                    name.with(highlight_colour).bold()
+                } else {
+                    name.with(text_colour)
+                });
+        }
+    }
+}
+
+/// How well `query` matches `candidate`, if it does at all. Every
+/// letter typed has to be in the candidate in the same order, letters
+/// next to each other and at the start of words count for more
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut from = 0;
+    let mut last = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = from + candidate[from..].iter().position(|&c| c == q)?;
+        score += 1;
+        if i > 0 && last == Some(i - 1) {
+            score += 4;
+        }
+        if i == 0 || candidate[i - 1] == ' ' {
+            score += 3;
+        }
+        last = Some(i);
+        from = i + 1;
+    }
+    Some(score)
+}
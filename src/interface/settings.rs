@@ -0,0 +1,553 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::{collections::BTreeSet, time::Duration};
+
+use chrono::NaiveTime;
+use crossterm::{
+    event::{KeyCode, KeyEvent},
+    style::{Color, Stylize},
+};
+
+use super::{canvas::Canvas, lil_guy};
+use crate::{
+    config::{CharacterChoice, Config},
+    mood::MoodConfig,
+    pet::Stage,
+    task::{Schedule, Task, TaskType},
+    unlockables::{Accessory, Appearance, Progress, Theme, Unlockable},
+};
+
+const COLOURS: [(&str, Color); 17] = [
+    ("Reset", Color::Reset),
+    ("Black", Color::Black),
+    ("DarkGrey", Color::DarkGrey),
+    ("Red", Color::Red),
+    ("DarkRed", Color::DarkRed),
+    ("Green", Color::Green),
+    ("DarkGreen", Color::DarkGreen),
+    ("Yellow", Color::Yellow),
+    ("DarkYellow", Color::DarkYellow),
+    ("Blue", Color::Blue),
+    ("DarkBlue", Color::DarkBlue),
+    ("Magenta", Color::Magenta),
+    ("DarkMagenta", Color::DarkMagenta),
+    ("Cyan", Color::Cyan),
+    ("DarkCyan", Color::DarkCyan),
+    ("White", Color::White),
+    ("Grey", Color::Grey),
+];
+
+/// Where the preview of the lil guy goes
+const PREVIEW_X: i32 = 56;
+
+const HELP: &str =
+    "Up/Down: move  Enter: edit  Left/Right: change  d: delete task  s: save  Esc: close";
+const EDIT_HELP: &str = "Enter: done  Esc: cancel";
+const TASK_HELP: &str = "Like \"Eat at 9:00, 18:00\" or \"Drink every 30min\"";
+
+/// What the settings screen wants done after a key press
+pub enum Outcome {
+    Stay,
+    /// Close without saving
+    Close,
+    /// Save the config and apply it
+    Save,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Row {
+    Character,
+    Accessory,
+    Theme,
+    Name,
+    Colour,
+    TextColour,
+    TaskColour,
+    TaskTimeout,
+    TaskTimeoutMax,
+    SnoozeDuration,
+    Mood,
+    Task(usize),
+    AddTask,
+}
+
+/// A screen for changing the config without having to find and
+/// edit the file
+pub struct SettingsScreen {
+    config: Config,
+    selected: usize,
+    /// What's been typed so far, when editing the selected row
+    editing: Option<String>,
+    /// Shown at the bottom when something couldn't be changed
+    message: Option<String>,
+    /// Can be picked, but not saved
+    locked: Vec<Unlockable>,
+    progress: Progress,
+    /// What the picked character looks like
+    preview: Vec<String>,
+    /// How grown up they are, for the preview
+    stage: Stage,
+}
+
+impl SettingsScreen {
+    pub fn new(config: Config, locked: Vec<Unlockable>, progress: Progress, stage: Stage) -> Self {
+        let mut settings = SettingsScreen {
+            config,
+            selected: 0,
+            editing: None,
+            message: None,
+            locked,
+            progress,
+            preview: Vec::new(),
+            stage,
+        };
+        settings.update_preview();
+        settings
+    }
+
+    fn update_preview(&mut self) {
+        self.preview = lil_guy::preview(self.config.character, self.stage).unwrap_or_default();
+    }
+
+    /// What's picked on a row, if it's something that can be locked
+    fn unlockable(&self, row: Row) -> Option<Unlockable> {
+        match row {
+            Row::Character => Some(Unlockable::Character(self.config.character)),
+            Row::Accessory => self.config.accessory.map(Unlockable::Accessory),
+            Row::Theme => self.config.theme.map(Unlockable::Theme),
+            _ => None,
+        }
+    }
+
+    /// Why a row can't be saved like it is
+    fn locked_reason(&self, row: Row) -> Option<String> {
+        let item = self
+            .unlockable(row)
+            .filter(|item| self.locked.contains(item))?;
+        let requirement = item.requirement()?;
+        Some(format!(
+            "{} is locked, it {}",
+            item.name(),
+            requirement.describe(&self.progress)
+        ))
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn into_config(self) -> Config {
+        self.config
+    }
+
+    pub fn set_message(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = vec![
+            Row::Character,
+            Row::Accessory,
+            Row::Theme,
+            Row::Name,
+            Row::Colour,
+            Row::TextColour,
+            Row::TaskColour,
+            Row::TaskTimeout,
+            Row::TaskTimeoutMax,
+            Row::SnoozeDuration,
+            Row::Mood,
+        ];
+        rows.extend((0..self.config.tasks.len()).map(Row::Task));
+        rows.push(Row::AddTask);
+        rows
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Outcome {
+        let row = self.rows()[self.selected];
+        if let Some(text) = &mut self.editing {
+            match key.code {
+                KeyCode::Char(c) => text.push(c),
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Esc => {
+                    self.editing = None;
+                    self.message = None;
+                }
+                KeyCode::Enter => {
+                    let text = text.clone();
+                    match self.set_text(row, text.trim()) {
+                        Ok(()) => {
+                            self.editing = None;
+                            self.message = None;
+                        }
+                        Err(e) => self.message = Some(e),
+                    }
+                }
+                _ => {}
+            }
+            return Outcome::Stay;
+        }
+
+        self.message = None;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Outcome::Close,
+            KeyCode::Char('s') => {
+                match [Row::Character, Row::Accessory, Row::Theme]
+                    .into_iter()
+                    .find_map(|row| self.locked_reason(row))
+                {
+                    Some(reason) => self.message = Some(reason),
+                    None => return Outcome::Save,
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.rows().len() - 1)
+            }
+            KeyCode::Left => self.cycle(row, -1),
+            KeyCode::Right => self.cycle(row, 1),
+            KeyCode::Enter => {
+                self.editing = Some(match row {
+                    Row::AddTask => String::new(),
+                    row => self.text(row),
+                })
+            }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                if let Row::Task(i) = row {
+                    let task = self.config.tasks.remove(i);
+                    self.config.keys.tasks.remove(task.ty().name());
+                }
+            }
+            _ => {}
+        }
+        // Say how to unlock whatever's picked on the new row
+        if self.message.is_none() {
+            self.message = self.locked_reason(self.rows()[self.selected]);
+        }
+        Outcome::Stay
+    }
+
+    fn label(row: Row) -> &'static str {
+        match row {
+            Row::Character => "Character",
+            Row::Accessory => "Accessory",
+            Row::Theme => "Theme",
+            Row::Name => "Name",
+            Row::Colour => "Colour",
+            Row::TextColour => "Text colour",
+            Row::TaskColour => "Task colour",
+            Row::TaskTimeout => "Task timeout",
+            Row::TaskTimeoutMax => "Max task timeout",
+            Row::SnoozeDuration => "Snooze for",
+            Row::Mood => "Mood",
+            Row::Task(_) => "Task",
+            Row::AddTask => "",
+        }
+    }
+
+    /// The current value of a row, written the way it'd be typed in
+    fn text(&self, row: Row) -> String {
+        let config = &self.config;
+        match row {
+            Row::Character => config.character.name().to_string(),
+            Row::Accessory => config.accessory.map_or("None", |a| a.name()).to_string(),
+            Row::Theme => config.theme.map_or("None", |t| t.name()).to_string(),
+            Row::Name => config.character_name.clone().unwrap_or_default(),
+            Row::Colour => colour_text(config.colour),
+            Row::TextColour => colour_text(config.text_colour),
+            Row::TaskColour => colour_text(config.task_colour),
+            Row::TaskTimeout => duration_text(config.task_timeout),
+            Row::TaskTimeoutMax => duration_text(config.task_timeout_max),
+            Row::SnoozeDuration => duration_text(config.snooze_duration),
+            Row::Mood => config.mood.name().to_string(),
+            Row::Task(i) => task_text(&config.tasks[i]),
+            Row::AddTask => "+ Add a task".to_string(),
+        }
+    }
+
+    fn set_text(&mut self, row: Row, text: &str) -> Result<(), String> {
+        let config = &mut self.config;
+        match row {
+            Row::Character => {
+                config.character = *CharacterChoice::ALL
+                    .iter()
+                    .find(|c| c.name().eq_ignore_ascii_case(text))
+                    .ok_or_else(|| format!("There's no character called {text}"))?;
+                self.update_preview();
+            }
+            Row::Accessory => {
+                config.accessory = parse_choice(&Accessory::ALL, Accessory::name, text)
+                    .map_err(|_| format!("There's no accessory called {text}"))?
+            }
+            Row::Theme => {
+                config.theme = parse_choice(&Theme::ALL, Theme::name, text)
+                    .map_err(|_| format!("There's no theme called {text}"))?
+            }
+            Row::Name => config.character_name = (!text.is_empty()).then(|| text.to_string()),
+            Row::Colour => config.colour = parse_colour(text)?,
+            Row::TextColour => config.text_colour = parse_colour(text)?,
+            Row::TaskColour => config.task_colour = parse_colour(text)?,
+            Row::TaskTimeout => config.task_timeout = parse_duration(text)?,
+            Row::TaskTimeoutMax => config.task_timeout_max = parse_duration(text)?,
+            Row::SnoozeDuration => config.snooze_duration = parse_duration(text)?,
+            Row::Mood => {
+                config.mood = *MoodConfig::ALL
+                    .iter()
+                    .find(|m| m.name().eq_ignore_ascii_case(text))
+                    .ok_or_else(|| format!("{text} isn't a mood, try Normal or Gentle"))?
+            }
+            Row::Task(i) => {
+                let mut task = parse_task(text)?;
+                // The amount and guide can't be typed in here, so they
+                // stay
+                task.amount = config.tasks[i].amount.clone();
+                task.guide = config.tasks[i].guide.clone();
+                // Keep its key if it got renamed
+                let old = config.tasks[i].ty().name();
+                if let Some(key) = config.keys.tasks.remove(old) {
+                    config.keys.tasks.insert(task.ty().name().to_string(), key);
+                }
+                config.tasks[i] = task;
+            }
+            Row::AddTask => {
+                config.tasks.push(parse_task(text)?);
+                // Stay on the "add" row for adding another
+                self.selected += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Switch between the choices for rows that have a fixed list
+    fn cycle(&mut self, row: Row, by: isize) {
+        fn next<T: PartialEq + Copy>(choices: &[T], current: T, by: isize) -> T {
+            let i = choices.iter().position(|c| *c == current).unwrap_or(0);
+            choices[(i as isize + by).rem_euclid(choices.len() as isize) as usize]
+        }
+        let config = &mut self.config;
+        let colours = COLOURS.map(|(_, c)| c);
+        match row {
+            Row::Character => {
+                config.character = next(&CharacterChoice::ALL, config.character, by);
+                self.update_preview();
+            }
+            Row::Accessory => {
+                let choices: Vec<_> = std::iter::once(None)
+                    .chain(Accessory::ALL.map(Some))
+                    .collect();
+                config.accessory = next(&choices, config.accessory, by);
+            }
+            Row::Theme => {
+                let choices: Vec<_> = std::iter::once(None).chain(Theme::ALL.map(Some)).collect();
+                config.theme = next(&choices, config.theme, by);
+            }
+            Row::Colour => config.colour = next(&colours, config.colour, by),
+            Row::TextColour => config.text_colour = next(&colours, config.text_colour, by),
+            Row::TaskColour => config.task_colour = next(&colours, config.task_colour, by),
+            Row::Mood => config.mood = next(&MoodConfig::ALL, config.mood, by),
+            _ => {}
+        }
+    }
+
+    pub fn render(&self, canvas: &mut Canvas, text_colour: Color, highlight_colour: Color) {
+        let screen_size = canvas.size();
+        canvas
+            .move_to(3, 1)
+            .print("Settings".with(highlight_colour).bold());
+        let rows = self.rows();
+        // Leave room for the title and the help at the bottom
+        let height = (screen_size.1 as usize).saturating_sub(7).max(1);
+        let scroll = (self.selected + 1).saturating_sub(height);
+        for (y, (i, &row)) in rows
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(height)
+            .enumerate()
+        {
+            let selected = i == self.selected;
+            let value = match (&self.editing, selected) {
+                (Some(text), true) => format!("{text}_"),
+                _ if self.locked_reason(row).is_some() => format!("{} (locked)", self.text(row)),
+                _ => self.text(row),
+            };
+            let label = match row {
+                Row::AddTask => String::new(),
+                row => format!("{}: ", Self::label(row)),
+            };
+            let line = format!("{} {label:<18}{value}", if selected { ">" } else { " " });
+            canvas.move_to(3, y as u16 + 3).print(if selected {
+                line.with(highlight_colour)
+            } else {
+                line.with(text_colour)
+            });
+        }
+
+        // Off to the side, if there's room
+        let hat = self.config.accessory.map_or(0, |a| a.lines().len());
+        let width = self
+            .preview
+            .iter()
+            .map(|line| line.len())
+            .max()
+            .unwrap_or(0);
+        if (screen_size.0 as usize) >= PREVIEW_X as usize + width + 2
+            && (screen_size.1 as usize) >= 3 + hat + self.preview.len() + 4
+        {
+            lil_guy::draw_frame(
+                canvas,
+                (PREVIEW_X, 3 + (hat + self.preview.len()) as i32),
+                &self.preview,
+                Appearance::new(&self.config),
+                self.config.colour,
+                &self.locked,
+            );
+        }
+
+        let help = match (&self.editing, rows[self.selected]) {
+            (Some(_), Row::Task(_) | Row::AddTask) => format!("{TASK_HELP}  {EDIT_HELP}"),
+            (Some(_), _) => EDIT_HELP.to_string(),
+            (None, _) => HELP.to_string(),
+        };
+        canvas
+            .move_to(3, screen_size.1.saturating_sub(3))
+            .print(
+                self.message
+                    .as_deref()
+                    .unwrap_or_default()
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .with(Color::Red),
+            )
+            .move_to(3, screen_size.1.saturating_sub(2))
+            .print(help.with(text_colour));
+    }
+}
+
+/// One of `choices` by name, or nothing for "None" or nothing typed
+fn parse_choice<T: Copy>(
+    choices: &[T],
+    name: impl Fn(&T) -> &'static str,
+    text: &str,
+) -> Result<Option<T>, ()> {
+    if text.is_empty() || text.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    choices
+        .iter()
+        .find(|c| name(c).eq_ignore_ascii_case(text))
+        .map(|c| Some(*c))
+        .ok_or(())
+}
+
+fn colour_text(colour: Color) -> String {
+    match colour {
+        Color::Rgb { r, g, b } => format!("#{r:02x}{g:02x}{b:02x}"),
+        Color::AnsiValue(v) => v.to_string(),
+        colour => COLOURS
+            .iter()
+            .find(|(_, c)| *c == colour)
+            .map_or("Reset", |(name, _)| name)
+            .to_string(),
+    }
+}
+
+/// Colours can be one of the names, `#rrggbb` or an ANSI colour number
+fn parse_colour(text: &str) -> Result<Color, String> {
+    if let Some((_, colour)) = COLOURS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(text))
+    {
+        return Ok(*colour);
+    }
+    if let Ok(v) = text.parse() {
+        return Ok(Color::AnsiValue(v));
+    }
+    match text
+        .strip_prefix('#')
+        .map(|hex| u32::from_str_radix(hex, 16))
+    {
+        Some(Ok(rgb)) if text.len() == 7 => Ok(Color::Rgb {
+            r: (rgb >> 16) as u8,
+            g: (rgb >> 8) as u8,
+            b: rgb as u8,
+        }),
+        _ => Err(format!(
+            "{text} isn't a colour, try a name like Red, #ff88cc or a number up to 255"
+        )),
+    }
+}
+
+fn duration_text(duration: Duration) -> String {
+    humantime::format_duration(duration).to_string()
+}
+
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    match humantime::parse_duration(text) {
+        Ok(d) if !d.is_zero() => Ok(d),
+        Ok(_) => Err("That's no time at all!".to_string()),
+        Err(e) => Err(format!(
+            "{text} isn't a length of time ({e}), try something like 30min"
+        )),
+    }
+}
+
+fn task_text(task: &Task) -> String {
+    match task.schedule() {
+        Schedule::Times(times) => format!(
+            "{} at {}",
+            task.ty().name(),
+            times
+                .iter()
+                .map(|t| t.format("%-H:%M").to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Schedule::Interval(interval) => {
+            format!("{} every {}", task.ty().name(), duration_text(interval))
+        }
+    }
+}
+
+/// Tasks are typed in like `Eat at 9:00, 18:00` or `Drink every 30min`
+fn parse_task(text: &str) -> Result<Task, String> {
+    let (name, schedule) = if let Some((name, interval)) = text.rsplit_once(" every ") {
+        (name, Schedule::Interval(parse_duration(interval.trim())?))
+    } else if let Some((name, times)) = text.rsplit_once(" at ") {
+        let times = times
+            .split(',')
+            .map(|t| {
+                let t = t.trim();
+                NaiveTime::parse_from_str(t, "%H:%M")
+                    .map_err(|_| format!("{t} isn't a time, try something like 9:00 or 18:30"))
+            })
+            .collect::<Result<BTreeSet<_>, _>>()?;
+        (name, Schedule::Times(times))
+    } else {
+        return Err(TASK_HELP.to_string());
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("The task needs a name".to_string());
+    }
+    Ok(Task::new(TaskType::from_name(name), schedule))
+}
@@ -0,0 +1,213 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use chrono::{DateTime, Datelike, Local};
+use crossterm::{
+    event::{KeyCode, KeyEvent},
+    style::{Color, Stylize},
+};
+
+use super::canvas::Canvas;
+use crate::{
+    achievements::Achievements,
+    history::{History, Status},
+    pet::Pet,
+    stats::{Day, Period, Streaks},
+    task::{Schedule, TaskType},
+};
+
+const HELP: &str = "Arrows: pick a day  PgUp/PgDn: scroll  Esc: close";
+
+/// Shades for a day in the heatmap, from none of it done on time to
+/// all of it
+const SHADES: [&str; 4] = ["░░", "▒▒", "▓▓", "██"];
+
+/// Where the heatmap starts, leaving room for the weekdays
+const HEATMAP_X: u16 = 7;
+
+/// What the stats screen wants done after a key press
+pub enum Outcome {
+    Stay,
+    Close,
+}
+
+/// How well each task has been getting done, covers up everything else
+pub struct StatsScreen {
+    periods: Vec<Period>,
+    /// Streaks and achievements
+    progress: Vec<String>,
+    days: Vec<Day>,
+    /// Which of the days is picked for showing its timeline
+    selected: usize,
+    scroll: usize,
+}
+
+impl StatsScreen {
+    pub fn new(
+        history: &History,
+        schedules: &[(TaskType, Schedule)],
+        achievements: &Achievements,
+        pet: &Pet,
+        now: DateTime<Local>,
+    ) -> Self {
+        let days = Day::all(history, schedules, now);
+        let mut progress = vec![format!("Age: {}", pet.describe()), String::new()];
+        progress.extend(Streaks::new(&Day::since_start(history, schedules, now)).lines());
+        progress.push(String::new());
+        progress.extend(achievements.lines());
+        StatsScreen {
+            periods: Period::all(history, schedules, now),
+            progress,
+            selected: days.len() - 1,
+            days,
+            scroll: 0,
+        }
+    }
+
+    /// Everything under the heatmap
+    fn lines(&self) -> Vec<String> {
+        let mut lines = self.timeline();
+        lines.push(String::new());
+        lines.extend(self.progress.iter().cloned());
+        for period in &self.periods {
+            lines.push(String::new());
+            lines.extend(period.lines());
+        }
+        lines
+    }
+
+    /// What happened on the selected day
+    fn timeline(&self) -> Vec<String> {
+        let day = &self.days[self.selected];
+        let mut lines = vec![match day.share() {
+            Some(share) => format!(
+                "{}: {} of {} on time ({:.0}%)",
+                day.date.format("%a %b %-d"),
+                day.total.on_time,
+                day.total.expected,
+                share * 100.0
+            ),
+            None => format!("{}: nothing to go by", day.date.format("%a %b %-d")),
+        }];
+        for entry in &day.timeline {
+            let what = match &entry.completion {
+                None => "not done".to_string(),
+                Some(completion) => {
+                    let at = completion.at.format("%H:%M");
+                    match completion.status {
+                        Status::OnTime => format!("on time, done {at}"),
+                        Status::Late => format!("late, done {at}"),
+                        Status::Missed => format!("missed, done {at}"),
+                        Status::Skipped => format!("skipped {at}"),
+                    }
+                }
+            };
+            let task: String = entry.task.chars().take(16).collect();
+            lines.push(format!(
+                "  {} {task:<16} {what}",
+                entry.time.format("%H:%M")
+            ));
+        }
+        lines
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Outcome {
+        let last = self.days.len() - 1;
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Outcome::Close,
+            // Weeks go across and days go down, like the days on a
+            // calendar
+            KeyCode::Left => self.selected = self.selected.saturating_sub(7),
+            KeyCode::Right => self.selected = (self.selected + 7).min(last),
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(last),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(5),
+            KeyCode::PageDown => {
+                self.scroll = (self.scroll + 5).min(self.lines().len().saturating_sub(1))
+            }
+            _ => return Outcome::Stay,
+        }
+        if !matches!(key.code, KeyCode::PageUp | KeyCode::PageDown) {
+            self.scroll = 0;
+        }
+        Outcome::Stay
+    }
+
+    pub fn render(&self, canvas: &mut Canvas, text_colour: Color, highlight_colour: Color) {
+        let screen_size = canvas.size();
+        canvas
+            .move_to(3, 1)
+            .print("Stats".with(highlight_colour).bold());
+        let below = self.render_heatmap(canvas, 3, text_colour, highlight_colour);
+        // Leave room for the help at the bottom
+        let height = screen_size.1.saturating_sub(below + 3) as usize;
+        for (y, line) in self
+            .lines()
+            .into_iter()
+            .skip(self.scroll)
+            .take(height)
+            .enumerate()
+        {
+            // Headings aren't indented
+            let line = if line.starts_with(' ') {
+                line.with(text_colour)
+            } else {
+                line.with(highlight_colour)
+            };
+            canvas.move_to(3, below + 1 + y as u16).print(line);
+        }
+        canvas
+            .move_to(3, screen_size.1.saturating_sub(2))
+            .print(HELP.with(text_colour));
+    }
+
+    /// A column for each week and a row for each day of the week,
+    /// returns the row after it
+    fn render_heatmap(
+        &self,
+        canvas: &mut Canvas,
+        y: u16,
+        text_colour: Color,
+        highlight_colour: Color,
+    ) -> u16 {
+        for (row, name) in [(0, "Mon"), (2, "Wed"), (4, "Fri"), (6, "Sun")] {
+            canvas.move_to(3, y + row).print(name.with(text_colour));
+        }
+        for (i, day) in self.days.iter().enumerate() {
+            let x = HEATMAP_X + (i / 7) as u16 * 3;
+            let row = day.date.weekday().num_days_from_monday() as u16;
+            canvas.move_to(x, y + row);
+            if i == self.selected {
+                canvas.print("[]".with(highlight_colour).bold());
+            } else if let Some(share) = day.share() {
+                let shade = ((share * SHADES.len() as f64) as usize).min(SHADES.len() - 1);
+                canvas.print(SHADES[shade].with(Color::Green));
+            } else {
+                canvas.print("··".with(text_colour));
+            }
+        }
+        canvas
+            .move_to(HEATMAP_X, y + 8)
+            .print("Less ".with(text_colour));
+        for shade in SHADES {
+            canvas.print(shade.with(Color::Green)).print(" ".stylize());
+        }
+        canvas.print("More".with(text_colour));
+        y + 9
+    }
+}
@@ -0,0 +1,81 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use chrono::{DateTime, Duration, Local};
+use crossterm::style::{Color, Stylize};
+
+use super::canvas::Canvas;
+use crate::{keybinds::Keybinds, task_manager::TaskDue};
+
+/// How much room the panel takes up on the right
+pub const WIDTH: u16 = 32;
+
+/// A list of what's coming up next down the right side of the
+/// screen, with how long until each one
+pub fn render(
+    canvas: &mut Canvas,
+    upcoming: &[TaskDue],
+    keybinds: &Keybinds,
+    now: DateTime<Local>,
+    (x, y): (u16, u16),
+    height: u16,
+    text_colour: Color,
+) {
+    if height < 2 {
+        return;
+    }
+    canvas
+        .move_to(x, y)
+        .print("Upcoming".with(text_colour).bold());
+    let mut upcoming: Vec<_> = upcoming.iter().collect();
+    upcoming.sort_by_key(|task| task.when);
+    for (i, task) in upcoming.into_iter().take(height as usize - 1).enumerate() {
+        let until = task.when - now;
+        let key = keybinds.task_key(&task.ty).map_or(' ', |key| key.0);
+        let name: String = task.ty.name().chars().take(16).collect();
+        canvas
+            .move_to(x, y + i as u16 + 1)
+            .print(format!("{key} ").with(text_colour))
+            .print(
+                format!("{name:<16} {:>9}", countdown(until))
+                    .with(soonness_colour(until, text_colour)),
+            );
+    }
+}
+
+/// Like `12m 30s` or `3h 05m`
+fn countdown(until: Duration) -> String {
+    let secs = until.num_seconds().max(0);
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    match hours {
+        0 if minutes == 0 => format!("{seconds}s"),
+        0 => format!("{minutes}m {seconds:02}s"),
+        1..24 => format!("{hours}h {minutes:02}m"),
+        _ => format!("{}d {}h", hours / 24, hours % 24),
+    }
+}
+
+/// Redder the sooner it is
+fn soonness_colour(until: Duration, text_colour: Color) -> Color {
+    match until.num_minutes() {
+        ..5 => Color::Red,
+        5..15 => Color::Yellow,
+        15..60 => Color::Cyan,
+        _ => text_colour,
+    }
+}
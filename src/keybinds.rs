@@ -0,0 +1,339 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    fmt,
+};
+
+use schemars::JsonSchema;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::task::{Task, TaskType};
+
+/// A single key on the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Key(pub char);
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Serialize for Key {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // YAML reads `1` as a number, but it's a perfectly good key
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum KeyDef {
+            Char(char),
+            Digit(u8),
+        }
+        match KeyDef::deserialize(deserializer) {
+            Ok(KeyDef::Char(c)) => Ok(Key(c)),
+            Ok(KeyDef::Digit(n @ 0..=9)) => Ok(Key((b'0' + n) as char)),
+            _ => Err(D::Error::custom(
+                "keys should be a single letter, number or symbol",
+            )),
+        }
+    }
+}
+
+/// Which keys do what, anything missing gets the default. Keys for
+/// things added since keys could be picked are optional, and only get
+/// their usual key if nothing else in the config has it, so configs
+/// from before them don't suddenly clash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct KeysConfig {
+    pub quit: Key,
+    pub snooze: Key,
+    /// Press this then a task's key to do it before it's due. Leave
+    /// this out for `x`, if it's free
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub early: Option<Key>,
+    /// Press this then a task's key to type in how much, for tasks
+    /// that keep track of an amount. Leave this out for `a`, if it's
+    /// free
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Key>,
+    /// Show or hide the upcoming tasks. Leave this out for `u`, if
+    /// it's free
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upcoming: Option<Key>,
+    pub settings: Key,
+    pub help: Key,
+    /// Leave this out for `:`, if it's free
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette: Option<Key>,
+    pub do_not_disturb: Key,
+    /// Leave this out for `i`, if it's free
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<Key>,
+    /// Keys for tasks, by the task's name. Tasks that aren't in here
+    /// get their usual key, or a number for custom tasks
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tasks: BTreeMap<String, Key>,
+}
+
+impl Default for KeysConfig {
+    fn default() -> Self {
+        KeysConfig {
+            quit: Key('q'),
+            snooze: Key('z'),
+            early: None,
+            amount: None,
+            upcoming: None,
+            settings: Key('o'),
+            help: Key('?'),
+            palette: None,
+            do_not_disturb: Key('n'),
+            stats: None,
+            tasks: BTreeMap::new(),
+        }
+    }
+}
+
+/// What a key does
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Binding {
+    Quit,
+    Snooze,
+    Early,
+    Amount,
+    Upcoming,
+    Settings,
+    Help,
+    Palette,
+    DoNotDisturb,
+    Stats,
+    Task(TaskType),
+}
+
+impl Binding {
+    /// A short name, for the footer
+    pub fn name(&self) -> &str {
+        match self {
+            Binding::Quit => "quit",
+            Binding::Snooze => "snooze",
+            Binding::Early => "do early",
+            Binding::Amount => "log amount",
+            Binding::Upcoming => "upcoming",
+            Binding::Settings => "settings",
+            Binding::Help => "help",
+            Binding::Palette => "commands",
+            Binding::DoNotDisturb => "do not disturb",
+            Binding::Stats => "stats",
+            Binding::Task(ty) => ty.name(),
+        }
+    }
+
+    /// What pressing the key does, to finish "Press 'x' to ..."
+    pub fn description(&self) -> &str {
+        match self {
+            Binding::Quit => "quit",
+            Binding::Snooze => "snooze a task",
+            Binding::Early => "do a task before it's due",
+            Binding::Amount => "log a different amount, like a big glass of water",
+            Binding::Upcoming => "show or hide upcoming tasks",
+            Binding::Settings => "open settings",
+            Binding::Help => "show all keys",
+            Binding::Palette => "search for a command",
+            Binding::DoNotDisturb => "turn do not disturb on or off",
+            Binding::Stats => "show how tasks have been going",
+            Binding::Task(ty) => ty.verb(),
+        }
+    }
+}
+
+/// Two things wanting the same key, or a key for a task that doesn't
+/// exist
+#[derive(Debug)]
+pub struct KeyConflict {
+    /// Where in the config it is, like `keys.quit`
+    pub path: String,
+    pub message: String,
+    pub suggestion: String,
+}
+
+/// Every key and what it does, worked out from the config
+#[derive(Debug, Default)]
+pub struct Keybinds {
+    keys: BTreeMap<Key, Binding>,
+    /// Things that didn't get their usual key because something else
+    /// has it
+    given_way: Vec<(Binding, Key)>,
+}
+
+impl Keybinds {
+    pub fn new(config: &KeysConfig, tasks: &[Task]) -> Result<Self, Vec<KeyConflict>> {
+        let mut keybinds = Keybinds::default();
+        let mut conflicts = Vec::new();
+        // Newer keys, with the key they get if they're left out
+        let newer = [
+            (config.palette, Key(':'), Binding::Palette, "palette"),
+            (config.early, Key('x'), Binding::Early, "early"),
+            (config.upcoming, Key('u'), Binding::Upcoming, "upcoming"),
+            (config.stats, Key('i'), Binding::Stats, "stats"),
+            (config.amount, Key('a'), Binding::Amount, "amount"),
+        ];
+        let picked = newer
+            .iter()
+            .filter_map(|(key, _, binding, name)| Some(((*key)?, binding.clone(), *name)));
+        for (key, binding, name) in [
+            (config.quit, Binding::Quit, "quit"),
+            (config.snooze, Binding::Snooze, "snooze"),
+            (config.settings, Binding::Settings, "settings"),
+            (config.help, Binding::Help, "help"),
+            (
+                config.do_not_disturb,
+                Binding::DoNotDisturb,
+                "do_not_disturb",
+            ),
+        ]
+        .into_iter()
+        .chain(picked)
+        {
+            keybinds.bind(key, binding, format!("keys.{name}"), &mut conflicts);
+        }
+        // Keys picked in the config go first, then the usual keys, so
+        // custom tasks only get the keys that are left over
+        for task in tasks {
+            let ty = task.ty();
+            if let Some(&key) = config.tasks.get(ty.name()) {
+                let path = format!("keys.tasks.{}", ty.name());
+                keybinds.bind(key, Binding::Task(ty.clone()), path, &mut conflicts);
+            }
+        }
+        let mut unbound = Vec::new();
+        for (i, task) in tasks.iter().enumerate() {
+            let ty = task.ty();
+            if config.tasks.contains_key(ty.name()) {
+                continue;
+            }
+            match ty.keybind() {
+                Some(key) => {
+                    let path = format!("tasks[{i}].type");
+                    keybinds.bind(Key(key), Binding::Task(ty.clone()), path, &mut conflicts);
+                }
+                None => unbound.push((i, ty)),
+            }
+        }
+        // Newer keys that weren't picked come after all the keys that
+        // were, and go without if their usual key is taken
+        for (key, usual, binding, _) in newer {
+            if key.is_some() {
+                continue;
+            }
+            match keybinds.keys.entry(usual) {
+                Entry::Occupied(_) => keybinds.given_way.push((binding, usual)),
+                Entry::Vacant(entry) => {
+                    entry.insert(binding);
+                }
+            }
+        }
+        // Numbers first, then any letters nobody's using. These go by
+        // the order in the config, so they don't move around while
+        // running
+        let mut free = ('1'..='9')
+            .chain('a'..='z')
+            .map(Key)
+            .filter(|key| !keybinds.keys.contains_key(key))
+            .collect::<Vec<_>>()
+            .into_iter();
+        for (i, ty) in unbound {
+            match free.next() {
+                Some(key) => keybinds.bind(
+                    key,
+                    Binding::Task(ty.clone()),
+                    format!("tasks[{i}]"),
+                    &mut conflicts,
+                ),
+                None => conflicts.push(KeyConflict {
+                    path: format!("tasks[{i}]"),
+                    message: format!("there are no keys left for {}", ty.name()),
+                    suggestion: "give it a key in `keys.tasks`".to_string(),
+                }),
+            }
+        }
+
+        for name in config.tasks.keys() {
+            if !tasks.iter().any(|task| task.ty().name() == name) {
+                conflicts.push(KeyConflict {
+                    path: format!("keys.tasks.{name}"),
+                    message: format!("there's no task called `{name}`"),
+                    suggestion: "use the same name as in `tasks`".to_string(),
+                });
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok(keybinds)
+        } else {
+            Err(conflicts)
+        }
+    }
+
+    fn bind(&mut self, key: Key, binding: Binding, path: String, conflicts: &mut Vec<KeyConflict>) {
+        if let Some(existing) = self.keys.get(&key) {
+            conflicts.push(KeyConflict {
+                path,
+                message: format!("`{key}` is already the key to {}", existing.description()),
+                suggestion: "pick a different key for one of them".to_string(),
+            });
+        } else {
+            self.keys.insert(key, binding);
+        }
+    }
+
+    pub fn get(&self, key: char) -> Option<&Binding> {
+        self.keys.get(&Key(key))
+    }
+
+    pub fn task_key(&self, ty: &TaskType) -> Option<Key> {
+        self.keys
+            .iter()
+            .find(|(_, binding)| **binding == Binding::Task(ty.clone()))
+            .map(|(key, _)| *key)
+    }
+
+    /// Keys for everything that isn't a task
+    pub fn global(&self) -> impl Iterator<Item = (Key, &Binding)> {
+        self.keys
+            .iter()
+            .filter(|(_, binding)| !matches!(binding, Binding::Task(_)))
+            .map(|(key, binding)| (*key, binding))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Key, &Binding)> {
+        self.keys.iter().map(|(key, binding)| (*key, binding))
+    }
+
+    /// Things without a key because their usual one is taken, and
+    /// what that key is
+    pub fn given_way(&self) -> impl Iterator<Item = (&Binding, Key)> {
+        self.given_way.iter().map(|(binding, key)| (binding, *key))
+    }
+}
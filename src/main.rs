@@ -20,21 +20,55 @@ along with Tuigotchi Health. If not, see
 
 use std::{fs::File, io::BufWriter, panic};
 
+use achievements::Achievements;
+use cli::Command;
 use color_eyre::{eyre::OptionExt, Result};
-use config::Config;
+use config::{Config, ConfigWatcher};
+use history::History;
 use interface::InterfaceState;
-use log::{error, warn};
+use log::{error, info, warn};
+use meds::Meds;
+use pet::Pet;
+use scheduler::Scheduler;
 use simplelog::WriteLogger;
 use task_manager::TaskManager;
+use unlockables::Unlocks;
 
+mod achievements;
+mod action;
+mod cli;
 mod config;
+#[cfg(all(unix, not(target_os = "macos")))]
+mod dbus;
+mod history;
+mod hooks;
+mod http_api;
 mod interface;
+mod keybinds;
+mod meds;
+mod mood;
+mod needs;
+mod notifier;
+mod pet;
+mod remote;
+mod scheduler;
+mod state_file;
+mod stats;
 mod task;
 mod task_manager;
+mod unlockables;
 
 fn not_main() -> Result<()> {
     color_eyre::install()?;
 
+    let dirs =
+        directories::ProjectDirs::from("ca.vedapowered", "Trans Girlies", "Tuigotchi Health")
+            .ok_or_eyre("Failed to load config dir!")?;
+    match Command::from_args()? {
+        Command::Run => {}
+        command => return command.run(&dirs),
+    }
+
     WriteLogger::init(
         simplelog::LevelFilter::Info,
         simplelog::Config::default(),
@@ -45,18 +79,62 @@ fn not_main() -> Result<()> {
         error!("Wheeeeeeee!!! PANIC UWUUU OWOOO (can you tell I had caffine before writing this message). Also, if you want, here's the message: {msg}");
     }));
 
-    let dirs =
-        directories::ProjectDirs::from("ca.vedapowered", "Trans Girlies", "Tuigotchi Health")
-            .ok_or_eyre("Failed to load config dir!")?;
-    let mut config = Config::load_config(dirs.config_dir())?;
-    let mut task_manager = TaskManager::new(&mut config)?;
-    let mut interface = InterfaceState::new(&config)?;
+    let mut config = Config::load_config(dirs.config_dir()).inspect_err(|e| {
+        // The TUI hasn't started yet, so this will actually be seen
+        eprintln!(
+            "There's a problem with {}:\n{e}",
+            Config::config_path(dirs.config_dir()).display()
+        );
+    })?;
+    let mut config_watcher = ConfigWatcher::new(Config::config_path(dirs.config_dir()));
+    // The interface needs the tasks for keybinds before the task
+    // manager takes them
+    let achievements = Achievements::load(
+        Achievements::path(dirs.data_dir()),
+        &config.achievements,
+        &config.medications,
+    )?;
+    let unlocks = Unlocks::load(Unlocks::path(dirs.data_dir()))?;
+    let pet = Pet::load(Pet::path(dirs.data_dir()), chrono::Local::now())?;
+    let meds = Meds::load(Meds::path(dirs.data_dir()), &config.medications)?;
+    let mut scheduler = Scheduler::new();
+    let mut interface = InterfaceState::new(
+        &config,
+        Config::config_path(dirs.config_dir()),
+        achievements,
+        unlocks,
+        pet,
+        meds,
+        scheduler.waker(),
+    )?;
+    scheduler.listen_for_input();
+    let history = History::load(History::path(dirs.data_dir()))?;
+    let mut task_manager = TaskManager::new(&mut config, history)?;
     let mut stdout = BufWriter::new(std::io::stdout());
-    while interface.update(&mut task_manager)? {
+    loop {
+        // Sleep until there's input or something else needs doing
+        interface.schedule(&mut scheduler);
+        scheduler.wake_at(config_watcher.next_check());
+        let event = scheduler.wait()?;
+        if !interface.update(event, &mut task_manager)? {
+            break;
+        }
+        if interface.take_saved_config() {
+            config_watcher.mark_seen();
+        }
+        match config_watcher.poll() {
+            Some(Ok(config)) => {
+                info!("Config changed, reloading");
+                if let Err(e) = interface.reload(config, &mut task_manager) {
+                    interface.set_config_error(e.to_string());
+                }
+            }
+            Some(Err(e)) => interface.set_config_error(e.to_string()),
+            None => {}
+        }
         if let Err(e) = interface.render(&mut stdout) {
             warn!("Rendering error: {e}");
         }
-        // Do other updates and stuff
     }
     Ok(())
 }
@@ -64,5 +142,6 @@ fn not_main() -> Result<()> {
 fn main() {
     if let Err(e) = not_main() {
         error!("AAAAAAAAAAAAAAAAAAAAA We crashed: {e}");
+        std::process::exit(1);
     }
 }
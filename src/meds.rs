@@ -0,0 +1,241 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! Medications, each with its own schedule. Every dose is a task of
+//! its own, so it gets a key, notifications and a line in the history
+//! like any other. Pill counts are saved next to the history.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use color_eyre::Result;
+use log::warn;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    state_file,
+    task::{Schedule, Task, TaskType},
+};
+
+/// A medication, from `medications` in the config
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MedicationConfig {
+    /// What it's called, this is the task's name too
+    pub name: String,
+    /// Like `50mg`, shown next to the name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dose: Option<String>,
+    pub schedule: Schedule,
+    /// How many pills come in a refill, to keep count of how many are
+    /// left. Without this they aren't counted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pills: Option<u32>,
+    /// How many pills one dose is
+    #[serde(default = "default_per_dose")]
+    pub per_dose: u32,
+    /// Remind you to refill when there's less than this many days left
+    #[serde(default = "default_refill_days")]
+    pub refill_days: u32,
+}
+
+fn default_per_dose() -> u32 {
+    1
+}
+
+fn default_refill_days() -> u32 {
+    7
+}
+
+impl MedicationConfig {
+    pub fn task_type(&self) -> TaskType {
+        TaskType::Other(self.name.clone())
+    }
+
+    pub fn task(&self) -> Task {
+        Task::new(self.task_type(), self.schedule.clone())
+    }
+
+    /// Like `Sertraline 50mg`
+    pub fn label(&self) -> String {
+        match &self.dose {
+            Some(dose) => format!("{} {dose}", self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Roughly, intervals are counted around the clock
+    fn doses_per_day(&self) -> f32 {
+        match &self.schedule {
+            Schedule::Times(times) => times.len() as f32,
+            Schedule::Interval(interval) => 86400.0 / interval.as_secs_f32().max(1.0),
+        }
+    }
+}
+
+pub struct Meds {
+    path: PathBuf,
+    all: Vec<MedicationConfig>,
+    /// How many pills are left, by name
+    pills: BTreeMap<String, u32>,
+}
+
+impl Meds {
+    const FILE: &str = "meds.json";
+
+    pub fn path(data_dir: &std::path::Path) -> PathBuf {
+        data_dir.join(Self::FILE)
+    }
+
+    /// Counted medications start on a full refill if there are no pill
+    /// counts yet, or the file with them is broken
+    pub fn load(path: PathBuf, configs: &[MedicationConfig]) -> Result<Self> {
+        let pills = state_file::load(&path)?.unwrap_or_default();
+        let mut meds = Meds {
+            path,
+            all: Vec::new(),
+            pills,
+        };
+        meds.reconfigure(configs);
+        Ok(meds)
+    }
+
+    /// Switch to the medications from a new config. New ones that are
+    /// counted start on a full refill
+    pub fn reconfigure(&mut self, configs: &[MedicationConfig]) {
+        self.all = configs.to_vec();
+        for med in configs {
+            if let Some(pills) = med.pills {
+                self.pills.entry(med.name.clone()).or_insert(pills);
+            }
+        }
+    }
+
+    /// The medication a task is for, if it's for one
+    pub fn get(&self, ty: &TaskType) -> Option<&MedicationConfig> {
+        self.all.iter().find(|med| med.task_type() == *ty)
+    }
+
+    /// Every medication that's counted, with how many pills are left
+    pub fn counted(&self) -> impl Iterator<Item = (&MedicationConfig, u32)> {
+        self.all
+            .iter()
+            .filter(|med| med.pills.is_some())
+            .filter_map(|med| Some((med, *self.pills.get(&med.name)?)))
+    }
+
+    /// How many days the pills will last, if they're counted
+    pub fn days_left(&self, med: &MedicationConfig) -> Option<f32> {
+        med.pills?;
+        let pills = *self.pills.get(&med.name)? as f32;
+        Some(pills / (med.per_dose as f32 * med.doses_per_day()))
+    }
+
+    pub fn is_low(&self, med: &MedicationConfig) -> bool {
+        self.days_left(med)
+            .is_some_and(|days| days < med.refill_days as f32)
+    }
+
+    /// Count a dose as taken. Returns what to say if that's what made
+    /// it run low
+    pub fn take(&mut self, ty: &TaskType) -> Option<String> {
+        let med = self.get(ty)?.clone();
+        let was_low = self.is_low(&med);
+        let pills = self.pills.get_mut(&med.name)?;
+        *pills = pills.saturating_sub(med.per_dose);
+        self.save_or_warn();
+        (!was_low && self.is_low(&med)).then(|| self.reminder(&med))
+    }
+
+    /// Like `Running low on Sertraline, 5 days left`. It's only out
+    /// once there really are no pills left
+    pub fn reminder(&self, med: &MedicationConfig) -> String {
+        let Some(days) = self.days_left(med) else {
+            return format!("Running low on {}", med.name);
+        };
+        if self.pills.get(&med.name) == Some(&0) {
+            return format!("Out of {}, time to refill!", med.name);
+        }
+        match days.ceil() as u32 {
+            _ if days < 1.0 => format!("Running low on {}, less than a day left", med.name),
+            1 => format!("Running low on {}, 1 day left", med.name),
+            n => format!("Running low on {}, {n} days left", med.name),
+        }
+    }
+
+    /// Add a full refill's worth of pills
+    pub fn refill(&mut self, name: &str) {
+        let Some(med) = self.all.iter().find(|med| med.name == name) else {
+            return;
+        };
+        let Some(refill) = med.pills else {
+            return;
+        };
+        let pills = self.pills.entry(med.name.clone()).or_default();
+        *pills = pills.saturating_add(refill);
+        self.save_or_warn();
+    }
+
+    fn save_or_warn(&self) {
+        if let Err(e) = self.save() {
+            warn!("Couldn't save to {}: {e}", self.path.display());
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        state_file::save(&self.path, &self.pills)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_out_when_there_are_no_pills() {
+        let med = MedicationConfig {
+            name: "Sertraline".to_string(),
+            dose: None,
+            schedule: Schedule::Times(
+                ["09:00:00", "21:00:00"]
+                    .iter()
+                    .map(|time| time.parse().unwrap())
+                    .collect(),
+            ),
+            pills: Some(30),
+            per_dose: 1,
+            refill_days: 7,
+        };
+        let mut meds = Meds {
+            path: PathBuf::new(),
+            all: vec![med.clone()],
+            pills: BTreeMap::new(),
+        };
+        let mut reminder = |pills| {
+            meds.pills.insert(med.name.clone(), pills);
+            meds.reminder(&med)
+        };
+        assert_eq!(reminder(0), "Out of Sertraline, time to refill!");
+        assert_eq!(
+            reminder(1),
+            "Running low on Sertraline, less than a day left"
+        );
+        assert_eq!(reminder(2), "Running low on Sertraline, 1 day left");
+        assert_eq!(reminder(3), "Running low on Sertraline, 2 days left");
+        assert_eq!(reminder(8), "Running low on Sertraline, 4 days left");
+    }
+}
@@ -0,0 +1,124 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! How the needs turn into a mood. The usual way lets the lil guy get
+//! very sad when things are left, which motivates some people and
+//! just makes a bad day worse for others.
+
+use crossterm::style::{Color, StyledContent, Stylize};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::needs::Needs;
+
+/// Works out how the lil guy feels
+pub trait MoodPolicy {
+    /// From 0 to 1
+    fn happiness(&self, needs: &Needs) -> f32;
+    /// What to call feeling `happiness`
+    fn mood(&self, happiness: f32) -> StyledContent<&'static str>;
+    /// Whether being down shows as being sad, otherwise he just looks
+    /// sleepy
+    fn shows_sadness(&self) -> bool {
+        true
+    }
+    /// Whether tasks left over from days before quietly move on to
+    /// their next time, instead of staying overdue
+    fn resets_missed(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum MoodConfig {
+    /// Sadder and sadder the longer things are left
+    #[default]
+    Normal,
+    /// Never worse than neutral, overdue tasks make him curious or
+    /// sleepy instead of sad, and missed days are left behind
+    Gentle,
+}
+
+impl MoodConfig {
+    pub const ALL: [MoodConfig; 2] = [MoodConfig::Normal, MoodConfig::Gentle];
+
+    /// The same as it's written in the config
+    pub fn name(&self) -> &'static str {
+        match self {
+            MoodConfig::Normal => "Normal",
+            MoodConfig::Gentle => "Gentle",
+        }
+    }
+
+    pub fn build(&self) -> Box<dyn MoodPolicy> {
+        match self {
+            MoodConfig::Normal => Box::new(NormalMood),
+            MoodConfig::Gentle => Box::new(GentleMood),
+        }
+    }
+}
+
+pub struct NormalMood;
+
+impl MoodPolicy for NormalMood {
+    fn happiness(&self, needs: &Needs) -> f32 {
+        needs.happiness()
+    }
+
+    fn mood(&self, happiness: f32) -> StyledContent<&'static str> {
+        match happiness {
+            ..=0.1 => "Very Sad".with(Color::DarkRed),
+            0.1..=0.4 => "Sad".with(Color::DarkMagenta),
+            0.4..=0.6 => "Neutral".with(Color::Grey),
+            0.6..=0.9 => "Happy".with(Color::Blue),
+            0.9.. => "Very Happy".with(Color::Green),
+            _ => "Unknown".with(Color::Magenta),
+        }
+    }
+}
+
+pub struct GentleMood;
+
+impl GentleMood {
+    /// As low as it goes, the bottom of neutral
+    const LOWEST: f32 = 0.4;
+}
+
+impl MoodPolicy for GentleMood {
+    fn happiness(&self, needs: &Needs) -> f32 {
+        needs.happiness().max(Self::LOWEST)
+    }
+
+    fn mood(&self, happiness: f32) -> StyledContent<&'static str> {
+        match happiness {
+            ..0.5 => "Sleepy".with(Color::DarkCyan),
+            0.5..0.6 => "Curious".with(Color::Cyan),
+            0.6..0.9 => "Happy".with(Color::Blue),
+            0.9.. => "Very Happy".with(Color::Green),
+            _ => "Unknown".with(Color::Magenta),
+        }
+    }
+
+    fn shows_sadness(&self) -> bool {
+        false
+    }
+
+    fn resets_missed(&self) -> bool {
+        true
+    }
+}
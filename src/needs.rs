@@ -0,0 +1,130 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! What the lil guy needs, worked out from how the tasks that look
+//! after each need are doing. Each one runs down as its tasks come
+//! due, and fills back up when they're done.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use chrono::{DateTime, Local};
+use serde::{Serialize, Serializer};
+
+use crate::task::TaskType;
+
+/// How full a need is when one of its tasks is due. It only drops
+/// below this once the task's overdue
+pub const DUE_LEVEL: f32 = 0.7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Need {
+    Hunger,
+    Thirst,
+    Energy,
+    Hygiene,
+    Health,
+}
+
+impl Need {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Need::Hunger => "Hunger",
+            Need::Thirst => "Thirst",
+            Need::Energy => "Energy",
+            Need::Hygiene => "Hygiene",
+            Need::Health => "Health",
+        }
+    }
+
+    /// Which need a task looks after. Tasks that aren't built in are
+    /// good for your health
+    pub fn of(ty: &TaskType) -> Need {
+        match ty {
+            TaskType::Eat => Need::Hunger,
+            TaskType::Drink => Need::Thirst,
+            TaskType::Sleep | TaskType::EyesRest => Need::Energy,
+            TaskType::Shower | TaskType::BrushTeeth | TaskType::Bathroom => Need::Hygiene,
+            TaskType::TakeMeds | TaskType::Other(_) => Need::Health,
+        }
+    }
+}
+
+/// How full each need is, from 0 to 1. Needs without any tasks
+/// aren't in here at all
+#[derive(Debug, Clone, Default)]
+pub struct Needs {
+    levels: BTreeMap<Need, f32>,
+}
+
+impl Needs {
+    /// From how looked after each task is (see [`level`]), a need is
+    /// only as full as its most neglected task
+    pub fn new<'a>(tasks: impl IntoIterator<Item = (&'a TaskType, f32)>) -> Self {
+        let mut levels: BTreeMap<Need, f32> = BTreeMap::new();
+        for (ty, level) in tasks {
+            levels
+                .entry(Need::of(ty))
+                .and_modify(|l| *l = l.min(level))
+                .or_insert(level);
+        }
+        Needs { levels }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Need, f32)> + '_ {
+        self.levels.iter().map(|(need, level)| (*need, *level))
+    }
+
+    /// The lowest need counts as much as all of them together, so one
+    /// really neglected need is enough to be sad about
+    pub fn happiness(&self) -> f32 {
+        if self.levels.is_empty() {
+            return 1.0;
+        }
+        let lowest = self.iter().map(|(_, level)| level).fold(1.0, f32::min);
+        let mean = self.levels.values().sum::<f32>() / self.levels.len() as f32;
+        (lowest + mean) / 2.0
+    }
+}
+
+impl Serialize for Needs {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter().map(|(need, level)| (need.name(), level)))
+    }
+}
+
+/// How looked after a task is, from 1 right after it's done, down to
+/// [`DUE_LEVEL`] when it's due. Then once it's been overdue for longer
+/// than `timeout`, down to 0 at `timeout` + `timeout_max`
+pub fn level(
+    last_done: DateTime<Local>,
+    due: DateTime<Local>,
+    now: DateTime<Local>,
+    timeout: Duration,
+    timeout_max: Duration,
+) -> f32 {
+    if now < due {
+        let between = (due - last_done).num_seconds().max(1) as f32;
+        let left = (due - now).num_seconds() as f32;
+        DUE_LEVEL + (1.0 - DUE_LEVEL) * (left / between).clamp(0.0, 1.0)
+    } else {
+        // Drops quickly at first, then slows down
+        let overdue =
+            ((now - due).num_seconds() as f32 - timeout.as_secs_f32()) / timeout_max.as_secs_f32();
+        DUE_LEVEL * (1.0 - overdue.max(0.0).sqrt()).max(0.0)
+    }
+}
@@ -0,0 +1,137 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::io::Write;
+
+use color_eyre::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{task::TaskType, task_manager::TaskDue};
+
+mod bell;
+mod command;
+mod desktop;
+mod terminal;
+
+pub use bell::BellNotifier;
+pub(crate) use command::shell_command;
+pub use command::CommandNotifier;
+pub use desktop::DesktopNotifier;
+pub use terminal::{OscKind, TerminalNotifier};
+
+/// Somewhere we can tell the user that the lil guy needs something
+pub trait Notifier {
+    /// Tell the user a task is due, `is_priority` is set once the
+    /// task has gone overdue
+    fn notify(&mut self, task: &TaskDue, is_priority: bool) -> Result<()>;
+    /// Tell the user something that isn't about a task, like an
+    /// achievement being unlocked
+    fn announce(&mut self, message: &str) -> Result<()>;
+    /// The task was done, so take back any notifications about it
+    fn dismiss(&mut self, _task: &TaskType) {}
+    /// Take back every notification we've sent, used when exiting
+    fn dismiss_all(&mut self) {}
+    /// Send anything that goes through the terminal. This is held on
+    /// to until a frame has been drawn, so it can't land in the middle
+    /// of one
+    fn write_pending(&mut self, _writer: &mut dyn Write) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Which notification backends to use, several can be enabled at once
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotifierConfig {
+    /// Freedesktop (or whatever the platform has) desktop notifications
+    Desktop,
+    /// Terminal escape sequences, these work over SSH in terminals
+    /// like kitty and WezTerm
+    Terminal(OscKind),
+    /// Just ring the terminal bell
+    Bell,
+    /// Run a shell command, info about the task is passed in
+    /// environment variables
+    Command(String),
+}
+
+impl NotifierConfig {
+    pub fn build(&self) -> Result<Box<dyn Notifier>> {
+        Ok(match self {
+            NotifierConfig::Desktop => Box::new(DesktopNotifier::new()?),
+            NotifierConfig::Terminal(kind) => Box::new(TerminalNotifier::new(*kind)),
+            NotifierConfig::Bell => Box::new(BellNotifier::default()),
+            NotifierConfig::Command(command) => Box::new(CommandNotifier::new(command.clone())),
+        })
+    }
+}
+
+/// All of the enabled notification backends
+pub struct Notifiers {
+    configs: Vec<NotifierConfig>,
+    backends: Vec<Box<dyn Notifier>>,
+}
+
+impl Notifiers {
+    pub fn new(configs: &[NotifierConfig]) -> Result<Self> {
+        Ok(Notifiers {
+            configs: configs.to_vec(),
+            backends: configs
+                .iter()
+                .map(NotifierConfig::build)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    pub fn configs(&self) -> &[NotifierConfig] {
+        &self.configs
+    }
+}
+
+impl Notifier for Notifiers {
+    /// Notify on every backend, one backend failing shouldn't stop the
+    /// others from getting the message
+    fn notify(&mut self, task: &TaskDue, is_priority: bool) -> Result<()> {
+        for backend in &mut self.backends {
+            if let Err(e) = backend.notify(task, is_priority) {
+                warn!("Failed to send notification: {e}");
+            }
+        }
+        Ok(())
+    }
+    fn announce(&mut self, message: &str) -> Result<()> {
+        for backend in &mut self.backends {
+            if let Err(e) = backend.announce(message) {
+                warn!("Failed to send notification: {e}");
+            }
+        }
+        Ok(())
+    }
+    fn dismiss(&mut self, task: &TaskType) {
+        self.backends.iter_mut().for_each(|b| b.dismiss(task));
+    }
+    fn dismiss_all(&mut self) {
+        self.backends.iter_mut().for_each(|b| b.dismiss_all());
+    }
+    fn write_pending(&mut self, writer: &mut dyn Write) -> Result<()> {
+        for backend in &mut self.backends {
+            backend.write_pending(writer)?;
+        }
+        Ok(())
+    }
+}
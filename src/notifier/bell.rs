@@ -0,0 +1,54 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::io::Write;
+
+use color_eyre::Result;
+
+use super::Notifier;
+use crate::task_manager::TaskDue;
+
+/// Ding!
+#[derive(Default)]
+pub struct BellNotifier {
+    /// Rung once the next frame has been drawn, however many things
+    /// wanted it
+    ringing: bool,
+}
+
+impl BellNotifier {
+    fn ring(&mut self) -> Result<()> {
+        self.ringing = true;
+        Ok(())
+    }
+}
+
+impl Notifier for BellNotifier {
+    fn notify(&mut self, _task: &TaskDue, _is_priority: bool) -> Result<()> {
+        self.ring()
+    }
+    fn announce(&mut self, _message: &str) -> Result<()> {
+        self.ring()
+    }
+    fn write_pending(&mut self, writer: &mut dyn Write) -> Result<()> {
+        if std::mem::take(&mut self.ringing) {
+            writer.write_all(b"\x07")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::process::{Command, Stdio};
+
+use color_eyre::Result;
+use log::warn;
+
+use super::Notifier;
+use crate::task_manager::TaskDue;
+
+/// Runs a shell command for every notification, with the task info in
+/// `TUIGOTCHI_TASK`, `TUIGOTCHI_MESSAGE`, `TUIGOTCHI_DUE` and
+/// `TUIGOTCHI_PRIORITY`. Ones that aren't about a task (achievements)
+/// only have `TUIGOTCHI_MESSAGE`
+pub struct CommandNotifier {
+    command: String,
+}
+
+impl CommandNotifier {
+    pub fn new(command: String) -> Self {
+        CommandNotifier { command }
+    }
+
+    fn run(&self, env: &[(&str, &str)]) -> Result<()> {
+        let mut child = shell_command(&self.command)
+            .envs(env.iter().copied())
+            // Anything it prints would end up all over the TUI
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        // Don't hold up the interface, but still clean up after it
+        std::thread::spawn(move || {
+            if let Err(e) = child.wait() {
+                warn!("Notification command failed: {e}");
+            }
+        });
+        Ok(())
+    }
+}
+
+impl Notifier for CommandNotifier {
+    fn notify(&mut self, task: &TaskDue, is_priority: bool) -> Result<()> {
+        self.run(&[
+            ("TUIGOTCHI_TASK", task.ty.name()),
+            ("TUIGOTCHI_MESSAGE", &task.ty.to_string()),
+            ("TUIGOTCHI_DUE", &task.when.to_rfc3339()),
+            ("TUIGOTCHI_PRIORITY", if is_priority { "1" } else { "0" }),
+        ])
+    }
+    fn announce(&mut self, message: &str) -> Result<()> {
+        self.run(&[("TUIGOTCHI_MESSAGE", message)])
+    }
+}
+
+/// Run a command through the platform's shell
+#[cfg(unix)]
+pub(crate) fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+pub(crate) fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
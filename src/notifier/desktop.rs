@@ -0,0 +1,105 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::{path::PathBuf, time::Duration};
+
+use color_eyre::Result;
+#[cfg(all(unix, not(target_os = "macos")))]
+use notify_rust::{Hint, NotificationHandle, Urgency};
+
+use super::Notifier;
+use crate::{task::TaskType, task_manager::TaskDue};
+
+const NOTIFY_APPNAME: &str = "tuigotchi-health";
+
+/// Desktop notifications through notify-rust
+pub struct DesktopNotifier {
+    temp_icon_path: PathBuf,
+    #[cfg(all(unix, not(target_os = "macos")))]
+    notifications: Vec<(TaskType, NotificationHandle)>,
+}
+
+impl DesktopNotifier {
+    pub fn new() -> Result<Self> {
+        let temp_icon_path = std::env::temp_dir().join("__kitty_notification_icon.png");
+        std::fs::write(&temp_icon_path, include_bytes!("../kitty_icon.png"))?;
+        Ok(DesktopNotifier {
+            temp_icon_path,
+            #[cfg(all(unix, not(target_os = "macos")))]
+            notifications: Vec::new(),
+        })
+    }
+}
+
+impl Notifier for DesktopNotifier {
+    // Some things go unused on non-Unix platforms due to missing
+    // features
+    #[cfg_attr(
+        not(all(unix, not(target_os = "macos"))),
+        allow(unused_mut, unused_variables)
+    )]
+    fn notify(&mut self, task: &TaskDue, is_priority: bool) -> Result<()> {
+        let mut notif = notify_rust::Notification::new()
+            .summary(&format!("{}", task.ty))
+            .appname(NOTIFY_APPNAME)
+            .timeout(Duration::from_secs(60))
+            .icon(&self.temp_icon_path.to_string_lossy())
+            .finalize();
+        #[cfg(all(unix, not(target_os = "macos")))]
+        let notif = notif.hint(Hint::Urgency(if is_priority {
+            Urgency::Critical
+        } else {
+            Urgency::Normal
+        }));
+
+        // Notification handle is unused on non-Unix platforms
+        #[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(unused_variables))]
+        let handle = notif.show()?;
+
+        #[cfg(all(unix, not(target_os = "macos")))]
+        self.notifications.push((task.ty.clone(), handle));
+
+        Ok(())
+    }
+
+    fn announce(&mut self, message: &str) -> Result<()> {
+        notify_rust::Notification::new()
+            .summary(message)
+            .appname(NOTIFY_APPNAME)
+            .timeout(Duration::from_secs(60))
+            .icon(&self.temp_icon_path.to_string_lossy())
+            .show()?;
+        Ok(())
+    }
+
+    #[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(unused_variables))]
+    fn dismiss(&mut self, task: &TaskType) {
+        // This would be so much nicer if retain was still drain_filter...
+        #[cfg(all(unix, not(target_os = "macos")))]
+        self.notifications
+            .extract_if(|(ty, _)| ty == task)
+            .for_each(|(_, n)| n.close());
+    }
+
+    fn dismiss_all(&mut self) {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        for (_, n) in self.notifications.drain(..) {
+            n.close();
+        }
+    }
+}
@@ -0,0 +1,84 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::io::Write;
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Notifier;
+use crate::task_manager::TaskDue;
+
+/// Which escape sequence to send, different terminals understand
+/// different ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OscKind {
+    /// `OSC 9`, understood by iTerm2, kitty, WezTerm, Windows Terminal...
+    #[serde(rename = "OSC 9")]
+    Osc9,
+    /// `OSC 777`, understood by urxvt, foot, kitty, WezTerm...
+    #[serde(rename = "OSC 777")]
+    Osc777,
+}
+
+/// Notifications sent as escape sequences to the terminal we're
+/// running in, so they go wherever the terminal is (even over SSH)
+pub struct TerminalNotifier {
+    kind: OscKind,
+    /// Escape sequences waiting for the next frame to be drawn
+    pending: Vec<u8>,
+}
+
+impl TerminalNotifier {
+    pub fn new(kind: OscKind) -> Self {
+        TerminalNotifier {
+            kind,
+            pending: Vec::new(),
+        }
+    }
+
+    fn send(&mut self, message: &str) -> Result<()> {
+        // Control characters would end the sequence early
+        let message: String = message
+            .chars()
+            .filter(|c| !c.is_control() && *c != ';')
+            .collect();
+        match self.kind {
+            OscKind::Osc9 => write!(self.pending, "\x1b]9;{message}\x07")?,
+            OscKind::Osc777 => write!(
+                self.pending,
+                "\x1b]777;notify;Tuigotchi Health;{message}\x07"
+            )?,
+        }
+        Ok(())
+    }
+}
+
+impl Notifier for TerminalNotifier {
+    fn notify(&mut self, task: &TaskDue, _is_priority: bool) -> Result<()> {
+        self.send(&task.ty.to_string())
+    }
+    fn announce(&mut self, message: &str) -> Result<()> {
+        self.send(message)
+    }
+    fn write_pending(&mut self, writer: &mut dyn Write) -> Result<()> {
+        writer.write_all(&self.pending)?;
+        self.pending.clear();
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! The lil guy growing up. Every day they're looked after they get a
+//! day older, a bad day only slows that down, and it's only after a
+//! few bad days in a row that it stops. They never get any younger.
+//! How old they are is saved next to the history.
+
+use std::path::PathBuf;
+
+use chrono::{DateTime, Days, Local, NaiveDate};
+use color_eyre::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    history::History,
+    state_file,
+    stats::Day,
+    task::{Schedule, TaskType},
+};
+
+/// How much of what was due has to get done for a day to count fully
+const GOOD_CARE: f64 = 0.5;
+/// Growing stops after this many bad days in a row, until a good one
+const NEGLECT_PAUSE: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Young,
+    Adult,
+    Elder,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 3] = [Stage::Young, Stage::Adult, Stage::Elder];
+
+    /// How old they have to be, in days of growing
+    fn age(&self) -> f64 {
+        match self {
+            Stage::Young => 0.0,
+            Stage::Adult => 14.0,
+            Stage::Elder => 60.0,
+        }
+    }
+
+    fn from_age(age: f64) -> Self {
+        [Stage::Elder, Stage::Adult]
+            .into_iter()
+            .find(|stage| age >= stage.age())
+            .unwrap_or(Stage::Young)
+    }
+
+    /// The same as the `stage` sections in the animation files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Young => "young",
+            Stage::Adult => "adult",
+            Stage::Elder => "elder",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Pet {
+    #[serde(skip)]
+    path: PathBuf,
+    born: NaiveDate,
+    /// Days of growing, which isn't the same as days since being born
+    age: f64,
+    /// The last day that's been counted towards growing
+    counted: NaiveDate,
+    /// Bad days in a row
+    neglected: u32,
+}
+
+impl Pet {
+    const FILE: &str = "pet.json";
+
+    pub fn path(data_dir: &std::path::Path) -> PathBuf {
+        data_dir.join(Self::FILE)
+    }
+
+    /// A new one is born today if there isn't one yet (or the old one's
+    /// file is broken)
+    pub fn load(path: PathBuf, now: DateTime<Local>) -> Result<Self> {
+        if let Some(pet) = state_file::load::<Pet>(&path)? {
+            return Ok(Pet { path, ..pet });
+        }
+        let today = now.date_naive();
+        let pet = Pet {
+            path,
+            born: today,
+            age: 0.0,
+            // Today only counts once it's over
+            counted: today - Days::new(1),
+            neglected: 0,
+        };
+        pet.save()?;
+        Ok(pet)
+    }
+
+    pub fn stage(&self) -> Stage {
+        Stage::from_age(self.age)
+    }
+
+    /// Like "12 days old, adult (born Oct 3 2026)"
+    pub fn describe(&self) -> String {
+        let days = self.age.floor();
+        format!(
+            "{days} {} old, {} (born {})",
+            if days == 1.0 { "day" } else { "days" },
+            self.stage().name(),
+            self.born.format("%b %-d %Y")
+        )
+    }
+
+    /// When the next day will be over and can be counted
+    pub fn next_growth(&self) -> DateTime<Local> {
+        (self.counted + Days::new(2))
+            .and_hms_opt(0, 0, 0)
+            .expect("Midnight exists")
+            .and_local_timezone(Local)
+            .earliest()
+            // No midnight on this day, thanks DST
+            .unwrap_or_else(|| Local::now() + chrono::Duration::hours(1))
+    }
+
+    /// Count every day that's over since last time. Returns the new
+    /// stage if they grew into one
+    pub fn grow(
+        &mut self,
+        history: &History,
+        schedules: &[(TaskType, Schedule)],
+        now: DateTime<Local>,
+    ) -> Option<Stage> {
+        let today = now.date_naive();
+        if self.counted + Days::new(1) >= today {
+            return None;
+        }
+        let stage = self.stage();
+        let days = Day::since(history, schedules, self.counted + Days::new(1), now);
+        for day in days.iter().filter(|day| day.date < today) {
+            // Days where nothing was due are good days
+            let care = if day.total.expected > 0 {
+                day.total.done as f64 / day.total.expected as f64
+            } else {
+                1.0
+            };
+            if care >= GOOD_CARE {
+                self.neglected = 0;
+                self.age += 1.0;
+            } else {
+                self.neglected += 1;
+                if self.neglected < NEGLECT_PAUSE {
+                    self.age += 0.5;
+                }
+            }
+        }
+        self.counted = today - Days::new(1);
+        if let Err(e) = self.save() {
+            warn!("Couldn't save to {}: {e}", self.path.display());
+        }
+        (self.stage() != stage).then(|| self.stage())
+    }
+
+    fn save(&self) -> Result<()> {
+        state_file::save(&self.path, self)
+    }
+}
@@ -0,0 +1,109 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+use std::sync::{
+    mpsc::{self, Receiver, Sender},
+    Arc, Mutex,
+};
+
+use serde::Serialize;
+
+use crate::{action::Action, needs::Needs, scheduler::Waker, task::TaskType, task_manager::Tasks};
+
+/// How the lil guy is doing, as seen from outside
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PetStatus {
+    pub name: String,
+    pub mood: String,
+    pub happiness: f32,
+    pub needs: Needs,
+    pub tasks: Tasks,
+}
+
+impl PetStatus {
+    /// Whether there's a task of this type at all, due or not
+    pub fn has_task(&self, ty: &TaskType) -> bool {
+        self.tasks
+            .past
+            .iter()
+            .chain(&self.tasks.current)
+            .chain(&self.tasks.upcoming)
+            .any(|task| &task.ty == ty)
+    }
+}
+
+/// The interface's end of remote control, it publishes the status
+/// and picks up actions sent from other threads
+pub struct Remote {
+    status: Arc<Mutex<PetStatus>>,
+    actions_tx: Sender<Action>,
+    actions: Receiver<Action>,
+    waker: Waker,
+}
+
+impl Remote {
+    /// `waker` gets the main loop to pick up actions as soon as
+    /// they're sent
+    pub fn new(waker: Waker) -> Self {
+        let (actions_tx, actions) = mpsc::channel();
+        Remote {
+            status: Arc::default(),
+            actions_tx,
+            actions,
+            waker,
+        }
+    }
+
+    /// A handle other threads can use to look at and poke the lil guy
+    pub fn handle(&self) -> RemoteHandle {
+        RemoteHandle {
+            status: self.status.clone(),
+            actions: self.actions_tx.clone(),
+            waker: self.waker.clone(),
+        }
+    }
+
+    pub fn set_status(&self, status: PetStatus) {
+        *self.status.lock().unwrap() = status;
+    }
+
+    /// All the actions that have come in since last time
+    pub fn actions(&self) -> impl Iterator<Item = Action> + '_ {
+        self.actions.try_iter()
+    }
+}
+
+#[derive(Clone)]
+pub struct RemoteHandle {
+    status: Arc<Mutex<PetStatus>>,
+    actions: Sender<Action>,
+    waker: Waker,
+}
+
+impl RemoteHandle {
+    pub fn status(&self) -> PetStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Returns false if the interface has gone away
+    pub fn send(&self, action: Action) -> bool {
+        let sent = self.actions.send(action).is_ok();
+        self.waker.wake();
+        sent
+    }
+}
@@ -0,0 +1,155 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! Working out how long the main loop can sleep for. Anything that
+//! needs doing at a certain time asks to be woken up for it, and the
+//! loop sleeps until the earliest one, or until there's input or
+//! something from another thread (like the HTTP API) wakes it.
+
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Local};
+use color_eyre::Result;
+use crossterm::event::{self, Event};
+
+/// Why the main loop was woken up early
+enum Wake {
+    Input(std::io::Result<Event>),
+    /// Something from another thread is waiting to be picked up
+    Poke,
+}
+
+/// Lets other threads wake the main loop up, when they've given it
+/// something to do
+#[derive(Clone)]
+pub struct Waker(Sender<Wake>);
+
+impl Waker {
+    pub fn wake(&self) {
+        // If the loop's gone there's nothing to wake
+        let _ = self.0.send(Wake::Poke);
+    }
+}
+
+pub struct Scheduler {
+    next: Option<Instant>,
+    wakes_tx: Sender<Wake>,
+    wakes: Receiver<Wake>,
+}
+
+impl Scheduler {
+    /// Never sleep longer than this, in case the clock jumps (like
+    /// after a suspend) and something's due sooner than we thought
+    const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+    pub fn new() -> Self {
+        let (wakes_tx, wakes) = mpsc::channel();
+        Scheduler {
+            next: None,
+            wakes_tx,
+            wakes,
+        }
+    }
+
+    /// Something other threads can use to wake the loop up
+    pub fn waker(&self) -> Waker {
+        Waker(self.wakes_tx.clone())
+    }
+
+    /// Read input from the terminal on its own thread, so it can wake
+    /// the loop up along with everything else
+    pub fn listen_for_input(&self) {
+        let wakes = self.wakes_tx.clone();
+        std::thread::spawn(move || loop {
+            let event = event::read();
+            let failed = event.is_err();
+            if wakes.send(Wake::Input(event)).is_err() || failed {
+                break;
+            }
+        });
+    }
+
+    pub fn wake_at(&mut self, at: Instant) {
+        self.next = Some(self.next.map_or(at, |next| next.min(at)));
+    }
+
+    /// Same thing but for wall clock times, like when a task is due
+    pub fn wake_at_time(&mut self, at: DateTime<Local>) {
+        let until = (at - Local::now()).to_std().unwrap_or_default();
+        self.wake_at(Instant::now() + until);
+    }
+
+    /// How long until something needs doing. Starts over afterwards,
+    /// everything has to ask again for the next round
+    pub fn take_timeout(&mut self) -> Duration {
+        self.next
+            .take()
+            .map_or(Self::MAX_SLEEP, |next| {
+                next.saturating_duration_since(Instant::now())
+            })
+            .min(Self::MAX_SLEEP)
+    }
+
+    /// Sleep until something needs doing, there's input, or another
+    /// thread wakes us up. Gives back the input, if that's what it was
+    pub fn wait(&mut self) -> Result<Option<Event>> {
+        let timeout = self.take_timeout();
+        match self.wakes.recv_timeout(timeout) {
+            Ok(Wake::Input(event)) => Ok(Some(event?)),
+            // We hold on to a sender ourselves, so it can only time out
+            Ok(Wake::Poke) | Err(_) => Ok(None),
+        }
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sleeps_a_while_when_nothing_is_due() {
+        let mut scheduler = Scheduler::new();
+        assert!(scheduler.take_timeout() > Duration::from_secs(1));
+        scheduler.wake_at(Instant::now() + Duration::from_secs(30));
+        assert!(scheduler.take_timeout() > Duration::from_secs(1));
+        // And it starts over afterwards
+        assert!(scheduler.take_timeout() > Duration::from_secs(30));
+    }
+
+    #[test]
+    fn wakes_up_when_poked() {
+        let mut scheduler = Scheduler::new();
+        let waker = scheduler.waker();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            waker.wake();
+        });
+        let start = Instant::now();
+        assert!(scheduler.wait().unwrap().is_none());
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
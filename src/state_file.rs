@@ -0,0 +1,54 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! The little JSON files in the data directory that keep track of
+//! things between runs, like achievements and pill counts
+
+use std::path::Path;
+
+use color_eyre::Result;
+use log::warn;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Read a state file, `None` if there isn't one yet. A broken one
+/// (say from a crash while saving) is treated as missing, losing it
+/// is better than not starting at all
+pub fn load<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => match serde_json::from_str(&text) {
+            Ok(state) => Ok(Some(state)),
+            Err(e) => {
+                warn!("Starting {} over, it's broken: {e}", path.display());
+                Ok(None)
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Write a state file, all at once so a crash can't leave half of it
+pub fn save(path: &Path, state: &impl Serialize) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, serde_json::to_string_pretty(state)?)?;
+    std::fs::rename(temp, path)?;
+    Ok(())
+}
@@ -0,0 +1,840 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! How well tasks have been getting done, worked out from the
+//! [`History`]
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Days, Duration, Local, NaiveDate, NaiveTime, Weekday};
+use serde::Serialize;
+
+use crate::{
+    history::{Completion, History, Status},
+    task::{Schedule, TaskType},
+};
+
+/// How many days back each period of stats goes
+pub const PERIODS: [i64; 2] = [7, 30];
+
+/// How many weeks back the heatmap goes
+pub const HEATMAP_WEEKS: u64 = 12;
+
+/// How wide the on time bar is
+const BAR_WIDTH: usize = 10;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskStats {
+    pub task: String,
+    /// Times it was due, whether it got done or not
+    pub total: usize,
+    pub on_time: usize,
+    pub late: usize,
+    /// Done too late to count, or never done at all
+    pub missed: usize,
+    pub skipped: usize,
+    /// How much of the total was on time, from 0 to 1
+    pub on_time_rate: f64,
+    /// How long after it was due it got done, on average. Skipped
+    /// ones don't count
+    pub average_delay_secs: i64,
+}
+
+/// Stats for every task that was due in the last `days` days
+#[derive(Debug, Clone, Serialize)]
+pub struct Period {
+    pub days: i64,
+    pub tasks: Vec<TaskStats>,
+}
+
+impl Period {
+    /// Goes by what the schedules expected each day, not just what got
+    /// done, so ignoring a task counts against it
+    pub fn new(
+        history: &History,
+        schedules: &[(TaskType, Schedule)],
+        now: DateTime<Local>,
+        days: i64,
+    ) -> Self {
+        let first = (now - Duration::days(days - 1)).date_naive();
+        let mut by_task: BTreeMap<String, (Vec<Completion>, usize)> = BTreeMap::new();
+        for day in Day::since(history, schedules, first, now) {
+            for (task, counts) in &day.tasks {
+                let completions: Vec<_> = day
+                    .timeline
+                    .iter()
+                    .filter(|entry| entry.task == *task)
+                    .filter_map(|entry| entry.completion.clone())
+                    .collect();
+                let (all, never_done) = by_task.entry(task.clone()).or_default();
+                *never_done += counts.expected.saturating_sub(completions.len());
+                all.extend(completions);
+            }
+        }
+        let tasks = by_task
+            .into_iter()
+            .filter(|(_, (completions, never_done))| completions.len() + never_done > 0)
+            .map(|(task, (completions, never_done))| {
+                let count = |status| completions.iter().filter(|c| c.status == status).count();
+                let delays: Vec<_> = completions
+                    .iter()
+                    .filter(|c| c.status != Status::Skipped)
+                    .map(|c| (c.at - c.due).num_seconds().max(0))
+                    .collect();
+                let on_time = count(Status::OnTime);
+                let total = completions.len() + never_done;
+                TaskStats {
+                    task,
+                    total,
+                    on_time,
+                    late: count(Status::Late),
+                    missed: count(Status::Missed) + never_done,
+                    skipped: count(Status::Skipped),
+                    on_time_rate: on_time as f64 / total as f64,
+                    average_delay_secs: delays.iter().sum::<i64>() / delays.len().max(1) as i64,
+                }
+            })
+            .collect();
+        Period { days, tasks }
+    }
+
+    /// Each of [`PERIODS`]
+    pub fn all(
+        history: &History,
+        schedules: &[(TaskType, Schedule)],
+        now: DateTime<Local>,
+    ) -> Vec<Self> {
+        PERIODS
+            .iter()
+            .map(|&days| Period::new(history, schedules, now, days))
+            .collect()
+    }
+
+    /// A little table with a bar for how often each task is on time
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Last {} days", self.days)];
+        if self.tasks.is_empty() {
+            lines.push("  Nothing done yet".to_string());
+            return lines;
+        }
+        lines.push(format!(
+            "  {:<16} {:<15} {:>9} {:>6} {:>7}",
+            "Task", "On time", "Avg delay", "Missed", "Skipped"
+        ));
+        for stats in &self.tasks {
+            let filled = (stats.on_time_rate * BAR_WIDTH as f64).round() as usize;
+            let bar = format!("{}{}", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled));
+            let name: String = stats.task.chars().take(16).collect();
+            lines.push(format!(
+                "  {name:<16} {bar} {:>3}% {:>9} {:>6} {:>7}",
+                (stats.on_time_rate * 100.0).round(),
+                delay(stats.average_delay_secs),
+                stats.missed,
+                stats.skipped,
+            ));
+        }
+        lines
+    }
+}
+
+/// Like `45s`, `12m` or `2h 05m`
+fn delay(secs: i64) -> String {
+    match secs {
+        ..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs / 60 % 60),
+    }
+}
+
+/// How a task went on a day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// How many times it should have been done, going by its schedule
+    pub expected: usize,
+    pub on_time: usize,
+    /// How many times it got done at all, on time or not
+    pub done: usize,
+    /// How many times it wasn't done before it was due again, or
+    /// never got done (or skipped) at all
+    pub missed: usize,
+}
+
+impl Counts {
+    /// Done on time every time it was expected to be, if it was
+    /// expected at all
+    pub fn kept(&self) -> Option<bool> {
+        (self.expected > 0).then_some(self.on_time >= self.expected)
+    }
+
+    /// Never missed, late or skipped is fine, if it was expected at
+    /// all
+    pub fn unmissed(&self) -> Option<bool> {
+        (self.expected > 0).then_some(self.missed == 0)
+    }
+}
+
+/// How one day went, for the heatmap
+#[derive(Debug, Clone)]
+pub struct Day {
+    pub date: NaiveDate,
+    /// Every task put together
+    pub total: Counts,
+    /// By the task's name
+    pub tasks: BTreeMap<String, Counts>,
+    /// Everything that was due, in order
+    pub timeline: Vec<TimelineEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TimelineEntry {
+    /// When it was due
+    pub time: NaiveTime,
+    pub task: String,
+    /// Nothing if it never got done
+    pub completion: Option<Completion>,
+}
+
+impl Day {
+    /// How much of what was expected got done on time, if anything
+    /// was expected at all
+    pub fn share(&self) -> Option<f64> {
+        (self.total.expected > 0)
+            .then(|| (self.total.on_time as f64 / self.total.expected as f64).min(1.0))
+    }
+
+    /// Every day from the Monday [`HEATMAP_WEEKS`] weeks back up to
+    /// today
+    pub fn all(
+        history: &History,
+        schedules: &[(TaskType, Schedule)],
+        now: DateTime<Local>,
+    ) -> Vec<Self> {
+        let today = now.date_naive();
+        let first = today.week(Weekday::Mon).first_day() - Days::new(7 * (HEATMAP_WEEKS - 1));
+        Self::since(history, schedules, first, now)
+    }
+
+    /// Every day from the start of the history up to today
+    pub fn since_start(
+        history: &History,
+        schedules: &[(TaskType, Schedule)],
+        now: DateTime<Local>,
+    ) -> Vec<Self> {
+        match Self::started(history) {
+            Some(started) => Self::since(history, schedules, started, now),
+            None => Vec::new(),
+        }
+    }
+
+    fn started(history: &History) -> Option<NaiveDate> {
+        history
+            .completions()
+            .iter()
+            .map(|c| c.due.date_naive().min(c.at.date_naive()))
+            .min()
+    }
+
+    /// Every day from `first` up to today. Days before the history
+    /// starts don't expect anything, since there's no way to know what
+    /// happened then
+    pub fn since(
+        history: &History,
+        schedules: &[(TaskType, Schedule)],
+        first: NaiveDate,
+        now: DateTime<Local>,
+    ) -> Vec<Self> {
+        let started = Self::started(history);
+        let mut by_date: BTreeMap<NaiveDate, Vec<&Completion>> = BTreeMap::new();
+        for completion in history.completions() {
+            by_date
+                .entry(completion.due.date_naive())
+                .or_default()
+                .push(completion);
+        }
+        first
+            .iter_days()
+            .take_while(|date| *date <= now.date_naive())
+            .map(|date| {
+                let started = started.is_some_and(|started| date >= started);
+                let completions = by_date.get(&date).map_or(&[][..], Vec::as_slice);
+                Day::new(completions, schedules, date, started.then_some(now))
+            })
+            .collect()
+    }
+
+    /// `now` is only given if the history had started by then, nothing
+    /// is expected otherwise
+    fn new(
+        completions: &[&Completion],
+        schedules: &[(TaskType, Schedule)],
+        date: NaiveDate,
+        now: Option<DateTime<Local>>,
+    ) -> Self {
+        let mut day = Day {
+            date,
+            total: Counts::default(),
+            tasks: BTreeMap::new(),
+            timeline: completions
+                .iter()
+                .map(|&c| TimelineEntry {
+                    time: c.due.time(),
+                    task: c.task.name().to_string(),
+                    completion: Some(c.clone()),
+                })
+                .collect(),
+        };
+        for completion in completions {
+            let counts = day
+                .tasks
+                .entry(completion.task.name().to_string())
+                .or_default();
+            match completion.status {
+                Status::OnTime => {
+                    counts.on_time += 1;
+                    counts.done += 1;
+                }
+                Status::Late => counts.done += 1,
+                Status::Missed => {
+                    counts.done += 1;
+                    counts.missed += 1;
+                }
+                Status::Skipped => {}
+            }
+            // Tasks that aren't in the config anymore still count for
+            // what was done
+            if now.is_some() && !schedules.iter().any(|(ty, _)| *ty == completion.task) {
+                counts.expected += 1;
+            }
+        }
+        if let Some(now) = now {
+            // Roughly when the lil guy was being looked after, from the
+            // first thing done that day until the last, or until now if
+            // it's today
+            let done_at = completions
+                .iter()
+                .map(|c| c.at)
+                .filter(|at| at.date_naive() == date);
+            let active_end = if date == now.date_naive() {
+                Some(now)
+            } else {
+                done_at.clone().max()
+            };
+            let active_start = done_at.min();
+            for (ty, schedule) in schedules {
+                let done: Vec<_> = completions.iter().filter(|c| c.task == *ty).collect();
+                let expected = match schedule {
+                    // Every time in the schedule that's already happened
+                    Schedule::Times(times) => {
+                        let slots: Vec<_> = times
+                            .iter()
+                            .filter(|&&time| date < now.date_naive() || time <= now.time())
+                            .collect();
+                        for &&slot in &slots {
+                            // Snoozing moves when it's due, so anything
+                            // before the next time counts
+                            let next = times.range(slot..).nth(1);
+                            let covered = done.iter().any(|c| {
+                                c.due.time() >= slot && next.is_none_or(|&next| c.due.time() < next)
+                            });
+                            if !covered {
+                                day.timeline.push(TimelineEntry {
+                                    time: slot,
+                                    task: ty.name().to_string(),
+                                    completion: None,
+                                });
+                            }
+                        }
+                        slots.len()
+                    }
+                    // Counting every interval in the day would expect it
+                    // while asleep, so only count the ones it was
+                    // actually due for, any that went by while it was
+                    // waiting, and any that came up after it was last
+                    // done while things were still getting done
+                    &Schedule::Interval(interval) => {
+                        let interval = interval.as_secs().max(1) as i64;
+                        let waited: usize = done
+                            .iter()
+                            .map(|c| 1 + ((c.at - c.due).num_seconds() / interval).max(0) as usize)
+                            .sum();
+                        let last = done.iter().map(|c| c.at).max().or(active_start);
+                        let since_last = last.zip(active_end).map_or(0, |(last, end)| {
+                            ((end - last).num_seconds() / interval).max(0)
+                        });
+                        waited + since_last as usize
+                    }
+                };
+                if expected > 0 {
+                    day.tasks.entry(ty.name().to_string()).or_default().expected += expected;
+                }
+            }
+        }
+        for (name, counts) in &mut day.tasks {
+            let recorded = completions.iter().filter(|c| c.task.name() == name).count();
+            counts.missed += counts.expected.saturating_sub(recorded);
+            day.total.expected += counts.expected;
+            day.total.on_time += counts.on_time;
+            day.total.done += counts.done;
+            day.total.missed += counts.missed;
+        }
+        day.timeline.sort_by_key(|entry| entry.time);
+        day
+    }
+}
+
+/// Days in a row of getting things done on time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Streak {
+    pub current: u32,
+    pub best: u32,
+}
+
+impl Streak {
+    /// Going by whatever `counts` picks out of each day. Days where
+    /// nothing was expected don't count either way, and today only
+    /// counts once it's all done, since there's still time
+    pub fn new(days: &[Day], counts: impl Fn(&Day) -> Counts) -> Self {
+        Self::kept_by(days, counts, Counts::kept)
+    }
+
+    /// Days in a row of not missing anything, instead of doing it all
+    /// on time
+    pub fn unmissed(days: &[Day], counts: impl Fn(&Day) -> Counts) -> Self {
+        Self::kept_by(days, counts, Counts::unmissed)
+    }
+
+    fn kept_by(
+        days: &[Day],
+        counts: impl Fn(&Day) -> Counts,
+        kept: impl Fn(&Counts) -> Option<bool>,
+    ) -> Self {
+        let mut streak = Streak::default();
+        let today = days.len().saturating_sub(1);
+        for (i, day) in days.iter().enumerate() {
+            match kept(&counts(day)) {
+                Some(true) => {
+                    streak.current += 1;
+                    streak.best = streak.best.max(streak.current);
+                }
+                Some(false) if i != today => streak.current = 0,
+                _ => {}
+            }
+        }
+        streak
+    }
+}
+
+/// Streaks for everything together, and for each task
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Streaks {
+    pub overall: Streak,
+    pub tasks: BTreeMap<String, Streak>,
+}
+
+impl Streaks {
+    /// `days` should be every day since the start of the history
+    pub fn new(days: &[Day]) -> Self {
+        let names: BTreeSet<_> = days.iter().flat_map(|day| day.tasks.keys()).collect();
+        Streaks {
+            overall: Streak::new(days, |day| day.total),
+            tasks: names
+                .into_iter()
+                .map(|name| {
+                    let streak =
+                        Streak::new(days, |day| day.tasks.get(name).copied().unwrap_or_default());
+                    (name.clone(), streak)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec!["Streaks".to_string()];
+        lines.push(format!("  {:<16} {:>7} {:>7}", "Task", "Current", "Best"));
+        for (name, streak) in std::iter::once(("Everything", &self.overall)).chain(
+            self.tasks
+                .iter()
+                .map(|(name, streak)| (name.as_str(), streak)),
+        ) {
+            let name: String = name.chars().take(16).collect();
+            lines.push(format!(
+                "  {name:<16} {:>6}d {:>6}d",
+                streak.current, streak.best
+            ));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, TimeZone};
+
+    use super::*;
+
+    /// June, so there's no DST change to worry about
+    fn at(day: u32, hour: u32, min: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2026, 6, day, hour, min, 0)
+            .single()
+            .unwrap()
+    }
+
+    fn done(
+        task: TaskType,
+        due: DateTime<Local>,
+        at: DateTime<Local>,
+        status: Status,
+    ) -> Completion {
+        Completion {
+            task,
+            due,
+            at,
+            status,
+            amount: None,
+        }
+    }
+
+    fn twice_a_day() -> Vec<(TaskType, Schedule)> {
+        let times = ["08:00:00", "20:00:00"].map(|t| t.parse().unwrap());
+        vec![(TaskType::Eat, Schedule::Times(times.into()))]
+    }
+
+    fn counts(day: &Day, task: &str) -> Counts {
+        day.tasks.get(task).copied().unwrap_or_default()
+    }
+
+    #[test]
+    fn today_only_expects_times_that_have_happened() {
+        let history = History::with(vec![done(
+            TaskType::Eat,
+            at(1, 8, 0),
+            at(1, 8, 5),
+            Status::OnTime,
+        )]);
+        let days = Day::since(
+            &history,
+            &twice_a_day(),
+            at(1, 0, 0).date_naive(),
+            at(2, 12, 0),
+        );
+        assert_eq!(days.len(), 2);
+        assert_eq!(counts(&days[0], "Eat").expected, 2);
+        assert_eq!(counts(&days[1], "Eat").expected, 1);
+        // Nobody ate at 20:00 on the first day, or this morning
+        let never_done: Vec<_> = days
+            .iter()
+            .flat_map(|day| &day.timeline)
+            .filter(|entry| entry.completion.is_none())
+            .map(|entry| entry.time.to_string())
+            .collect();
+        assert_eq!(never_done, ["20:00:00", "08:00:00"]);
+    }
+
+    #[test]
+    fn nothing_is_expected_before_the_history_starts() {
+        let history = History::with(vec![done(
+            TaskType::Eat,
+            at(3, 8, 0),
+            at(3, 8, 0),
+            Status::OnTime,
+        )]);
+        let days = Day::since(
+            &history,
+            &twice_a_day(),
+            at(1, 0, 0).date_naive(),
+            at(3, 9, 0),
+        );
+        assert_eq!(days[0].total, Counts::default());
+        assert_eq!(days[1].total, Counts::default());
+        assert_eq!(days[2].total.expected, 1);
+    }
+
+    #[test]
+    fn done_after_midnight_counts_for_the_day_it_was_due() {
+        let history = History::with(vec![done(
+            TaskType::Eat,
+            at(1, 20, 0),
+            at(2, 0, 30),
+            Status::Late,
+        )]);
+        let days = Day::since(
+            &history,
+            &twice_a_day(),
+            at(1, 0, 0).date_naive(),
+            at(2, 7, 0),
+        );
+        // Breakfast was missed, but dinner got done, just late
+        let first = counts(&days[0], "Eat");
+        assert_eq!(
+            (first.expected, first.done, first.on_time, first.missed),
+            (2, 1, 0, 1)
+        );
+        assert!(days[0]
+            .timeline
+            .iter()
+            .any(|entry| entry.time.to_string() == "20:00:00" && entry.completion.is_some()));
+        // It's not 8 yet, so nothing's due today
+        assert_eq!(counts(&days[1], "Eat"), Counts::default());
+    }
+
+    #[test]
+    fn intervals_only_expect_the_hours_things_were_done() {
+        let hourly = vec![(
+            TaskType::Drink,
+            Schedule::Interval(std::time::Duration::from_secs(3600)),
+        )];
+        let history = History::with(vec![
+            done(TaskType::Drink, at(1, 9, 0), at(1, 9, 0), Status::OnTime),
+            // Left for a while, so a couple went by while it waited
+            done(TaskType::Drink, at(1, 10, 0), at(1, 12, 30), Status::Missed),
+            done(TaskType::Drink, at(2, 9, 0), at(2, 9, 0), Status::OnTime),
+        ]);
+        let days = Day::since(&history, &hourly, at(1, 0, 0).date_naive(), at(2, 11, 30));
+        // 1 + 3, and nothing for the night
+        assert_eq!(counts(&days[0], "Drink").expected, 4);
+        // Today goes until now, so two more have come up since 9
+        assert_eq!(counts(&days[1], "Drink").expected, 3);
+    }
+
+    #[test]
+    fn never_done_counts_as_missed() {
+        let history = History::with(vec![
+            done(TaskType::Eat, at(1, 8, 0), at(1, 8, 0), Status::OnTime),
+            done(TaskType::Eat, at(2, 8, 0), at(2, 9, 0), Status::Late),
+        ]);
+        let period = Period::new(&history, &twice_a_day(), at(2, 21, 0), 2);
+        let [eat] = &period.tasks[..] else {
+            panic!("Expected just Eat, got {:?}", period.tasks);
+        };
+        assert_eq!(eat.total, 4);
+        assert_eq!((eat.on_time, eat.late, eat.missed), (1, 1, 2));
+        assert_eq!(eat.on_time_rate, 0.25);
+        assert_eq!(eat.average_delay_secs, 30 * 60);
+    }
+
+    #[test]
+    fn periods_only_go_back_so_far() {
+        let history = History::with(vec![
+            done(TaskType::Eat, at(1, 8, 0), at(1, 8, 0), Status::OnTime),
+            done(TaskType::Eat, at(1, 20, 0), at(1, 20, 0), Status::OnTime),
+        ]);
+        let period = Period::new(&history, &twice_a_day(), at(3, 12, 0), 1);
+        // Just this morning's, which nobody ate
+        assert_eq!(period.tasks[0].total, 1);
+        assert_eq!(period.tasks[0].missed, 1);
+    }
+
+    #[test]
+    fn a_bad_day_breaks_the_streak() {
+        let history = History::with(
+            [1, 2, 4]
+                .into_iter()
+                .flat_map(|day| {
+                    [8, 20].map(|hour| {
+                        done(
+                            TaskType::Eat,
+                            at(day, hour, 0),
+                            at(day, hour, 0),
+                            Status::OnTime,
+                        )
+                    })
+                })
+                .collect(),
+        );
+        let days = Day::since_start(&history, &twice_a_day(), at(4, 21, 0));
+        let streak = Streak::new(&days, |day| day.total);
+        assert_eq!(
+            streak,
+            Streak {
+                current: 1,
+                best: 2
+            }
+        );
+    }
+
+    #[test]
+    fn late_days_dont_break_an_unmissed_streak() {
+        let history = History::with(
+            [1, 2, 3, 4]
+                .into_iter()
+                .flat_map(|day| {
+                    [8, 20].map(|hour| {
+                        // Late on the 2nd, and never got to dinner on
+                        // the 3rd
+                        let status = match (day, hour) {
+                            (2, _) => Status::Late,
+                            (3, 20) => Status::Missed,
+                            _ => Status::OnTime,
+                        };
+                        done(TaskType::Eat, at(day, hour, 0), at(day, hour, 0), status)
+                    })
+                })
+                .collect(),
+        );
+        let days = Day::since_start(&history, &twice_a_day(), at(4, 21, 0));
+        assert_eq!(
+            Streak::unmissed(&days, |day| day.total),
+            Streak {
+                current: 1,
+                best: 2
+            }
+        );
+        assert_eq!(Streak::new(&days, |day| day.total).best, 1);
+    }
+
+    /// Eat and Shower every day from the 1st up to `last`, except what
+    /// `leave_out` says
+    fn every_day(last: u32, leave_out: impl Fn(&Completion) -> bool) -> History {
+        History::with(
+            (1..=last)
+                .flat_map(|day| {
+                    [
+                        done(TaskType::Eat, at(day, 8, 0), at(day, 8, 0), Status::OnTime),
+                        done(
+                            TaskType::Eat,
+                            at(day, 20, 0),
+                            at(day, 20, 0),
+                            Status::OnTime,
+                        ),
+                        done(
+                            TaskType::Shower,
+                            at(day, 9, 0),
+                            at(day, 9, 0),
+                            Status::OnTime,
+                        ),
+                    ]
+                })
+                .filter(|c| !leave_out(c))
+                .collect(),
+        )
+    }
+
+    fn eat_and_shower() -> Vec<(TaskType, Schedule)> {
+        let mut schedules = twice_a_day();
+        schedules.push((
+            TaskType::Shower,
+            Schedule::Times(["09:00:00".parse().unwrap()].into()),
+        ));
+        schedules
+    }
+
+    fn streaks_for(history: &History, now: DateTime<Local>) -> Streaks {
+        Streaks::new(&Day::since_start(history, &eat_and_shower(), now))
+    }
+
+    #[test]
+    fn missing_a_day_only_breaks_that_tasks_streak() {
+        let history = every_day(5, |c| c.task == TaskType::Shower && c.due.day() == 3);
+        let streaks = streaks_for(&history, at(5, 21, 0));
+        assert_eq!(
+            streaks.tasks["Eat"],
+            Streak {
+                current: 5,
+                best: 5
+            }
+        );
+        assert_eq!(
+            streaks.tasks["Shower"],
+            Streak {
+                current: 2,
+                best: 2
+            }
+        );
+        assert_eq!(
+            streaks.overall,
+            Streak {
+                current: 2,
+                best: 2
+            }
+        );
+    }
+
+    #[test]
+    fn skipping_doesnt_keep_a_streak_going() {
+        let history = History::with(
+            every_day(4, |_| false)
+                .completions()
+                .iter()
+                .cloned()
+                .map(|c| match c.task {
+                    TaskType::Shower if c.due.day() == 2 => Completion {
+                        status: Status::Skipped,
+                        ..c
+                    },
+                    _ => c,
+                })
+                .collect(),
+        );
+        let streaks = streaks_for(&history, at(4, 21, 0));
+        assert_eq!(
+            streaks.tasks["Shower"],
+            Streak {
+                current: 2,
+                best: 2
+            }
+        );
+        assert_eq!(
+            streaks.overall,
+            Streak {
+                current: 2,
+                best: 2
+            }
+        );
+    }
+
+    #[test]
+    fn today_doesnt_break_a_streak_until_its_over() {
+        // Dinner isn't done yet, but there's still time
+        let history = every_day(3, |c| c.due == at(3, 20, 0));
+        let streaks = streaks_for(&history, at(3, 21, 0));
+        assert_eq!(
+            streaks.tasks["Eat"],
+            Streak {
+                current: 2,
+                best: 2
+            }
+        );
+        assert_eq!(
+            streaks.overall,
+            Streak {
+                current: 2,
+                best: 2
+            }
+        );
+        // Once it's done it counts
+        let streaks = streaks_for(&every_day(3, |_| false), at(3, 21, 0));
+        assert_eq!(
+            streaks.tasks["Eat"],
+            Streak {
+                current: 3,
+                best: 3
+            }
+        );
+        // And once the day's over it's too late
+        let history = every_day(3, |c| c.due == at(3, 20, 0));
+        let streaks = streaks_for(&history, at(4, 7, 0));
+        assert_eq!(
+            streaks.tasks["Eat"],
+            Streak {
+                current: 0,
+                best: 2
+            }
+        );
+    }
+}
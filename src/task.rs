@@ -20,18 +20,77 @@ use chrono::DurationRound;
 use chrono::{DateTime, Duration, Local, NaiveTime};
 use color_eyre::eyre::OptionExt;
 use color_eyre::Result;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 use std::fmt;
 use std::ops::Bound;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::config::HumanDuration;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Task {
     #[serde(rename = "type")]
     ty: TaskType,
     schedule: Schedule,
+    /// Keep track of how much, like ml of water, instead of just
+    /// whether it got done
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Amount>,
+    /// A countdown to follow along with before it counts as done, like
+    /// looking away for 20 seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guide: Option<Guide>,
     #[serde(default = "Local::now", skip)]
     pub last_done: DateTime<Local>,
+    #[serde(skip)]
+    pub snoozed_until: Option<DateTime<Local>>,
+}
+
+/// How much doing a task counts for, and how much to aim for each day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Amount {
+    /// What it's counted in, like `ml` or `meals`
+    pub unit: String,
+    /// How much doing it once is, like the size of your water bottle
+    pub per: u32,
+    /// How much to get through in a day
+    pub target: u32,
+}
+
+impl Amount {
+    /// Like `750/2000 ml`
+    pub fn describe(&self, done: u32) -> String {
+        format!("{done}/{} {}", self.target, self.unit)
+    }
+}
+
+/// A guided timer, for things like resting your eyes, stretching or
+/// breathing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Guide {
+    /// How long it goes for
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "HumanDuration")]
+    pub duration: std::time::Duration,
+    /// What to do the whole time, like `Look at something 20 feet away`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+    /// Steps to go through, round and round until the time's up
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<GuideStep>,
+    /// Let a key end it early, still counting it as done
+    #[serde(default)]
+    pub finish_early: bool,
+}
+
+/// One part of a guided timer, like `Breathe in` for 4 seconds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct GuideStep {
+    pub text: String,
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "HumanDuration")]
+    pub duration: std::time::Duration,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -72,6 +131,35 @@ impl fmt::Display for TaskType {
 }
 
 impl TaskType {
+    /// The name of the task type, the same as it's written in the config
+    pub fn name(&self) -> &str {
+        match self {
+            TaskType::Eat => "Eat",
+            TaskType::Drink => "Drink",
+            TaskType::BrushTeeth => "Brush Teeth",
+            TaskType::Shower => "Shower",
+            TaskType::EyesRest => "Eyes Rest",
+            TaskType::TakeMeds => "Take Meds",
+            TaskType::Sleep => "Sleep",
+            TaskType::Bathroom => "Bathroom",
+            TaskType::Other(d) => d,
+        }
+    }
+    /// The opposite of [`TaskType::name`], anything we don't know
+    /// about is a custom task
+    pub fn from_name(name: &str) -> TaskType {
+        match name {
+            "Eat" => TaskType::Eat,
+            "Drink" => TaskType::Drink,
+            "Brush Teeth" => TaskType::BrushTeeth,
+            "Shower" => TaskType::Shower,
+            "Eyes Rest" => TaskType::EyesRest,
+            "Take Meds" => TaskType::TakeMeds,
+            "Sleep" => TaskType::Sleep,
+            "Bathroom" => TaskType::Bathroom,
+            other => TaskType::Other(other.to_string()),
+        }
+    }
     pub fn keybind(&self) -> Option<char> {
         match self {
             TaskType::Eat => Some('e'),
@@ -101,6 +189,17 @@ impl TaskType {
 }
 
 impl Task {
+    pub fn new(ty: TaskType, schedule: Schedule) -> Self {
+        Task {
+            ty,
+            schedule,
+            amount: None,
+            guide: None,
+            last_done: Local::now(),
+            snoozed_until: None,
+        }
+    }
+
     pub fn ty(&self) -> &TaskType {
         &self.ty
     }
@@ -111,6 +210,11 @@ impl Task {
 
     pub fn complete(&mut self, now: DateTime<Local>) {
         self.last_done = now;
+        self.snoozed_until = None;
+    }
+
+    pub fn snooze(&mut self, until: DateTime<Local>) {
+        self.snoozed_until = Some(until);
     }
 }
 
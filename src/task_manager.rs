@@ -16,25 +16,40 @@ along with Tuigotchi Health. If not, see
 <https://www.gnu.org/licenses/>.
 */
 
-use chrono::{DateTime, Duration, Local};
+use chrono::{DateTime, Days, Duration, Local};
 
 use crate::config::Config;
-use crate::task::{Task, TaskType};
+use crate::history::{Completion, History, Status};
+use crate::meds::MedicationConfig;
+use crate::task::{Amount, Guide, Schedule, Task, TaskType};
 
 use color_eyre::Result;
+use serde::{Serialize, Serializer};
 
 pub struct TaskManager {
     tasks: Vec<Task>,
     task_threshold: Duration,
+    history: History,
 }
 
-#[derive(Debug, PartialEq)]
+/// From [`TaskManager::prepare_reload`]
+pub struct NewTasks {
+    tasks: Vec<Task>,
+    task_threshold: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct TaskDue {
+    #[serde(rename = "task", serialize_with = "serialize_task_name")]
     pub ty: TaskType,
     pub when: DateTime<Local>,
 }
 
-#[derive(Debug, Default)]
+fn serialize_task_name<S: Serializer>(ty: &TaskType, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(ty.name())
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct Tasks {
     pub past: Vec<TaskDue>,
     pub current: Vec<TaskDue>,
@@ -42,13 +57,47 @@ pub struct Tasks {
 }
 
 impl TaskManager {
-    pub fn new(config: &mut Config) -> Result<Self> {
+    pub fn new(config: &mut Config, history: History) -> Result<Self> {
+        let NewTasks {
+            tasks,
+            task_threshold,
+        } = Self::prepare_reload(config)?;
         Ok(Self {
-            tasks: std::mem::take(&mut config.tasks),
-            task_threshold: Duration::from_std(config.task_timeout)?,
+            tasks,
+            task_threshold,
+            history,
         })
     }
 
+    /// Get the tasks from a new config ready to switch to, this is
+    /// everything that can go wrong with [`TaskManager::reload`]
+    pub fn prepare_reload(config: &mut Config) -> Result<NewTasks> {
+        let task_threshold = Duration::from_std(config.task_timeout)?;
+        let mut tasks = std::mem::take(&mut config.tasks);
+        tasks.extend(config.medications.iter().map(MedicationConfig::task));
+        Ok(NewTasks {
+            tasks,
+            task_threshold,
+        })
+    }
+
+    /// Switch to the tasks from a new config, keeping track of when
+    /// each type of task was last done
+    pub fn reload(&mut self, new: NewTasks) {
+        let NewTasks {
+            mut tasks,
+            task_threshold,
+        } = new;
+        for task in &mut tasks {
+            if let Some(old) = self.tasks.iter().find(|t| t.ty() == task.ty()) {
+                task.last_done = old.last_done;
+                task.snoozed_until = old.snoozed_until;
+            }
+        }
+        self.tasks = tasks;
+        self.task_threshold = task_threshold;
+    }
+
     pub fn tasks(&self, now: DateTime<Local>) -> Result<Tasks> {
         let mut tasks = Tasks {
             past: vec![],
@@ -57,15 +106,7 @@ impl TaskManager {
         };
 
         for task in &self.tasks {
-            let task_due = TaskDue {
-                ty: task.ty().clone(),
-                // We actually want to find the "next instance" in
-                // relation to when it was last done, rather than now;
-                // this gives the time when the task *should* be done,
-                // or should have been done
-                when: task.schedule().next_instance(task.last_done)?,
-            };
-
+            let task_due = Self::due(task)?;
             if task_due.when > now {
                 tasks.upcoming.push(task_due);
             } else if now - task_due.when < self.task_threshold {
@@ -78,10 +119,184 @@ impl TaskManager {
         Ok(tasks)
     }
 
-    pub fn complete_tasks(&mut self, ty: &TaskType, now: DateTime<Local>) {
+    /// When `tasks` would next give something different from `current`,
+    /// so there's no point asking before then. With `resets_missed`,
+    /// that includes when [`TaskManager::reset_missed`] would next move
+    /// something on
+    pub fn next_change(&self, current: &Tasks, resets_missed: bool) -> Option<DateTime<Local>> {
+        let resets = self
+            .tasks
+            .iter()
+            .filter(|_| resets_missed)
+            .filter_map(|task| Self::reset_at(task).ok().flatten());
+        current
+            .upcoming
+            .iter()
+            .map(|task| task.when)
+            .chain(
+                current
+                    .current
+                    .iter()
+                    .map(|task| task.when + self.task_threshold),
+            )
+            .chain(resets)
+            .min()
+    }
+
+    /// When [`TaskManager::reset_missed`] would move a task on, once
+    /// it's come round again and it's a new day
+    fn reset_at(task: &Task) -> Result<Option<DateTime<Local>>> {
+        let due = Self::due(task)?.when;
+        let again = task.schedule().next_instance(due)?;
+        let tomorrow = (due.date_naive() + Days::new(1))
+            .and_hms_opt(0, 0, 0)
+            .expect("Midnight exists")
+            .and_local_timezone(Local)
+            .earliest();
+        Ok(tomorrow.map(|tomorrow| again.max(tomorrow)))
+    }
+
+    fn due(task: &Task) -> Result<TaskDue> {
+        // We actually want to find the "next instance" in relation to
+        // when it was last done, rather than now; this gives the time
+        // when the task *should* be done, or should have been done
+        let when = task.schedule().next_instance(task.last_done)?;
+        Ok(TaskDue {
+            ty: task.ty().clone(),
+            // Snoozing pushes it back, but never earlier than it would
+            // be anyways
+            when: task.snoozed_until.map_or(when, |until| until.max(when)),
+        })
+    }
+
+    /// Every task type and its schedule
+    pub fn schedules(&self) -> Vec<(TaskType, Schedule)> {
+        self.tasks
+            .iter()
+            .map(|task| (task.ty().clone(), task.schedule()))
+            .collect()
+    }
+
+    /// When a task was last done, or when we started if it hasn't
+    /// been
+    pub fn last_done(&self, ty: &TaskType) -> Option<DateTime<Local>> {
+        self.tasks
+            .iter()
+            .find(|task| task.ty() == ty)
+            .map(|task| task.last_done)
+    }
+
+    /// The guided timer to do a task with, if it has one
+    pub fn guide(&self, ty: &TaskType) -> Option<Guide> {
+        self.tasks
+            .iter()
+            .find(|task| task.ty() == ty)
+            .and_then(|task| task.guide.clone())
+    }
+
+    /// Tasks that keep track of an amount, with how much has been done
+    /// today
+    pub fn amounts(&self, now: DateTime<Local>) -> Vec<(TaskType, Amount, u32)> {
+        self.tasks
+            .iter()
+            .filter_map(|task| {
+                let amount = task.amount.clone()?;
+                let done = self.history.amount_on(task.ty(), now.date_naive());
+                Some((task.ty().clone(), amount, done))
+            })
+            .collect()
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Do a task. Tasks that keep track of an amount log `amount`, or
+    /// their usual amount if it's `None`
+    pub fn complete_tasks(
+        &mut self,
+        ty: &TaskType,
+        now: DateTime<Local>,
+        amount: Option<u32>,
+    ) -> Result<()> {
+        self.record(ty, now, false, amount)?;
         self.tasks
             .iter_mut()
             .filter(|t| t.ty() == ty)
             .for_each(|t| Task::complete(t, now));
+        Ok(())
+    }
+
+    /// Move on to the next time a task is due without doing it. The
+    /// schedule and the needs meters treat that the same as doing it,
+    /// the difference is that it's kept as skipped in the history, so
+    /// it doesn't count for stats or achievements and there's no
+    /// celebrating
+    pub fn skip_tasks(&mut self, ty: &TaskType, now: DateTime<Local>) -> Result<()> {
+        self.record(ty, now, true, None)?;
+        self.tasks
+            .iter_mut()
+            .filter(|t| t.ty() == ty)
+            .for_each(|t| Task::complete(t, now));
+        Ok(())
+    }
+
+    /// Put how it went in the history, before the task moves on
+    fn record(
+        &mut self,
+        ty: &TaskType,
+        now: DateTime<Local>,
+        skipped: bool,
+        amount: Option<u32>,
+    ) -> Result<()> {
+        let mut completions = Vec::new();
+        for task in self.tasks.iter().filter(|t| t.ty() == ty) {
+            let due = Self::due(task)?.when;
+            let status = if skipped {
+                Status::Skipped
+            } else if now < due + self.task_threshold {
+                Status::OnTime
+            } else if now < task.schedule().next_instance(due)? {
+                Status::Late
+            } else {
+                Status::Missed
+            };
+            completions.push(Completion {
+                task: ty.clone(),
+                due,
+                at: now,
+                status,
+                amount: match &task.amount {
+                    Some(a) if !skipped => Some(amount.unwrap_or(a.per)),
+                    _ => None,
+                },
+            });
+        }
+        for completion in completions {
+            self.history.record(completion);
+        }
+        Ok(())
+    }
+
+    /// Move tasks that were due on an earlier day, and have come round
+    /// again since, on to their next time. Nothing goes in the history,
+    /// since they weren't done. Returns the ones that moved
+    pub fn reset_missed(&mut self, now: DateTime<Local>) -> Result<Vec<TaskType>> {
+        let mut reset = Vec::new();
+        for task in &mut self.tasks {
+            let due = Self::due(task)?.when;
+            if due.date_naive() < now.date_naive() && task.schedule().next_instance(due)? <= now {
+                task.complete(now);
+                reset.push(task.ty().clone());
+            }
+        }
+        Ok(reset)
+    }
+
+    pub fn snooze_tasks(&mut self, ty: &TaskType, until: DateTime<Local>) {
+        self.tasks
+            .iter_mut()
+            .filter(|t| t.ty() == ty)
+            .for_each(|t| t.snooze(until));
     }
 }
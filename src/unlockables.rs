@@ -0,0 +1,442 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! Extra characters, things to wear and colour themes, earned with
+//! points or streaks. Like achievements, once something's unlocked it
+//! stays unlocked, even if the streak breaks.
+
+use std::{collections::BTreeSet, path::PathBuf};
+
+use chrono::{DateTime, Local};
+use color_eyre::Result;
+use crossterm::style::Color;
+use log::warn;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::Achievements,
+    config::{CharacterChoice, Config},
+    history::{History, Status},
+    state_file,
+    stats::{Day, Streaks},
+    task::{Schedule, TaskType},
+};
+
+/// Points for each achievement, on top of one per task done on time
+const ACHIEVEMENT_POINTS: u32 = 10;
+
+/// Worn on the lil guy's head
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Accessory {
+    Flower,
+    Bow,
+    #[serde(rename = "Party Hat")]
+    PartyHat,
+    #[serde(rename = "Top Hat")]
+    TopHat,
+    Crown,
+}
+
+impl Accessory {
+    pub const ALL: [Accessory; 5] = [
+        Accessory::Flower,
+        Accessory::Bow,
+        Accessory::PartyHat,
+        Accessory::TopHat,
+        Accessory::Crown,
+    ];
+
+    /// The same as it's written in the config
+    pub fn name(&self) -> &'static str {
+        match self {
+            Accessory::Flower => "Flower",
+            Accessory::Bow => "Bow",
+            Accessory::PartyHat => "Party Hat",
+            Accessory::TopHat => "Top Hat",
+            Accessory::Crown => "Crown",
+        }
+    }
+
+    /// Drawn just above the top of each frame, centred on the head
+    pub fn lines(&self) -> &'static [&'static str] {
+        match self {
+            Accessory::Flower => &["\\@/"],
+            Accessory::Bow => &[">o<"],
+            Accessory::PartyHat => &["  o", " / \\", "/___\\"],
+            Accessory::TopHat => &[" ___", " | |", "====="],
+            Accessory::Crown => &["/\\/\\/\\", "|____|"],
+        }
+    }
+
+    pub fn colour(&self) -> Color {
+        match self {
+            Accessory::Flower => Color::Red,
+            Accessory::Bow => Color::Magenta,
+            Accessory::PartyHat => Color::Cyan,
+            Accessory::TopHat => Color::Grey,
+            Accessory::Crown => Color::Yellow,
+        }
+    }
+}
+
+/// Pride flags, drawn as stripes down the lil guy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum Theme {
+    Rainbow,
+    Trans,
+    Bi,
+    Lesbian,
+    #[serde(rename = "Non-binary")]
+    NonBinary,
+    Ace,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 6] = [
+        Theme::Rainbow,
+        Theme::Trans,
+        Theme::Bi,
+        Theme::Lesbian,
+        Theme::NonBinary,
+        Theme::Ace,
+    ];
+
+    /// The same as it's written in the config
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Rainbow => "Rainbow",
+            Theme::Trans => "Trans",
+            Theme::Bi => "Bi",
+            Theme::Lesbian => "Lesbian",
+            Theme::NonBinary => "Non-binary",
+            Theme::Ace => "Ace",
+        }
+    }
+
+    /// Top to bottom
+    pub fn stripes(&self) -> &'static [Color] {
+        const fn rgb(rgb: u32) -> Color {
+            Color::Rgb {
+                r: (rgb >> 16) as u8,
+                g: (rgb >> 8) as u8,
+                b: rgb as u8,
+            }
+        }
+        const RAINBOW: [Color; 6] = [
+            rgb(0xe40303),
+            rgb(0xff8c00),
+            rgb(0xffed00),
+            rgb(0x008026),
+            rgb(0x004dff),
+            rgb(0x750787),
+        ];
+        const TRANS: [Color; 5] = [
+            rgb(0x5bcefa),
+            rgb(0xf5a9b8),
+            rgb(0xffffff),
+            rgb(0xf5a9b8),
+            rgb(0x5bcefa),
+        ];
+        const BI: [Color; 3] = [rgb(0xd60270), rgb(0x9b4f96), rgb(0x0038a8)];
+        const LESBIAN: [Color; 5] = [
+            rgb(0xd52d00),
+            rgb(0xff9a56),
+            rgb(0xffffff),
+            rgb(0xd362a4),
+            rgb(0xa30262),
+        ];
+        const NON_BINARY: [Color; 4] = [rgb(0xfcf434), rgb(0xffffff), rgb(0x9c59d1), rgb(0x5c5c5c)];
+        // Black would vanish on most terminals
+        const ACE: [Color; 4] = [rgb(0x5c5c5c), rgb(0xa3a3a3), rgb(0xffffff), rgb(0x800080)];
+        match self {
+            Theme::Rainbow => &RAINBOW,
+            Theme::Trans => &TRANS,
+            Theme::Bi => &BI,
+            Theme::Lesbian => &LESBIAN,
+            Theme::NonBinary => &NON_BINARY,
+            Theme::Ace => &ACE,
+        }
+    }
+
+    /// The colour for line `y` of something `height` lines tall
+    pub fn colour(&self, y: usize, height: usize) -> Color {
+        let stripes = self.stripes();
+        stripes[(y * stripes.len() / height.max(1)).min(stripes.len() - 1)]
+    }
+}
+
+/// How the lil guy looks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Appearance {
+    pub character: CharacterChoice,
+    pub accessory: Option<Accessory>,
+    pub theme: Option<Theme>,
+}
+
+impl Appearance {
+    /// Straight from the config, whether it's unlocked or not
+    pub fn new(conf: &Config) -> Self {
+        Appearance {
+            character: conf.character,
+            accessory: conf.accessory,
+            theme: conf.theme,
+        }
+    }
+}
+
+/// Anything that can be locked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unlockable {
+    Character(CharacterChoice),
+    Accessory(Accessory),
+    Theme(Theme),
+}
+
+/// What it takes to unlock something
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+    Points(u32),
+    /// Days in a row, for any task or for everything
+    Streak(u32),
+}
+
+impl Requirement {
+    fn met(&self, progress: &Progress) -> bool {
+        match *self {
+            Requirement::Points(points) => progress.points >= points,
+            Requirement::Streak(days) => progress.best_streak >= days,
+        }
+    }
+
+    pub fn describe(&self, progress: &Progress) -> String {
+        match self {
+            Requirement::Points(points) => {
+                format!("needs {points} points (you have {})", progress.points)
+            }
+            Requirement::Streak(days) => format!(
+                "needs a {days} day streak (your best is {})",
+                progress.best_streak
+            ),
+        }
+    }
+}
+
+impl Unlockable {
+    /// Everything that starts off locked
+    pub fn all() -> Vec<Unlockable> {
+        CharacterChoice::ALL
+            .map(Unlockable::Character)
+            .into_iter()
+            .chain(Accessory::ALL.map(Unlockable::Accessory))
+            .chain(Theme::ALL.map(Unlockable::Theme))
+            .filter(|item| item.requirement().is_some())
+            .collect()
+    }
+
+    /// Nothing for things everyone starts with
+    pub fn requirement(&self) -> Option<Requirement> {
+        Some(match self {
+            Unlockable::Character(
+                CharacterChoice::DebugGuy | CharacterChoice::Kitty | CharacterChoice::Puppy,
+            ) => return None,
+            Unlockable::Character(CharacterChoice::Lizard) => Requirement::Points(50),
+            Unlockable::Character(CharacterChoice::AutismCreature) => Requirement::Streak(7),
+            Unlockable::Accessory(Accessory::Flower) => Requirement::Points(10),
+            Unlockable::Accessory(Accessory::Bow) => Requirement::Points(25),
+            Unlockable::Accessory(Accessory::PartyHat) => Requirement::Streak(3),
+            Unlockable::Accessory(Accessory::TopHat) => Requirement::Points(100),
+            Unlockable::Accessory(Accessory::Crown) => Requirement::Streak(14),
+            Unlockable::Theme(Theme::Rainbow | Theme::Trans) => Requirement::Points(10),
+            Unlockable::Theme(Theme::Bi | Theme::Lesbian) => Requirement::Points(20),
+            Unlockable::Theme(Theme::NonBinary | Theme::Ace) => Requirement::Streak(3),
+        })
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Unlockable::Character(character) => character.name().to_string(),
+            Unlockable::Accessory(accessory) => accessory.name().to_string(),
+            Unlockable::Theme(theme) => format!("{} theme", theme.name()),
+        }
+    }
+
+    /// How it's saved, so names in different kinds can't clash
+    fn id(&self) -> String {
+        match self {
+            Unlockable::Character(character) => format!("character/{}", character.name()),
+            Unlockable::Accessory(accessory) => format!("accessory/{}", accessory.name()),
+            Unlockable::Theme(theme) => format!("theme/{}", theme.name()),
+        }
+    }
+}
+
+/// How far along things are, for working out what's unlocked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// One for each task done on time, plus some for each achievement
+    pub points: u32,
+    /// The best streak for anything
+    pub best_streak: u32,
+}
+
+impl Progress {
+    pub fn new(
+        history: &History,
+        schedules: &[(TaskType, Schedule)],
+        achievements: &Achievements,
+        now: DateTime<Local>,
+    ) -> Self {
+        let streaks = Streaks::new(&Day::since_start(history, schedules, now));
+        let on_time = history
+            .completions()
+            .iter()
+            .filter(|c| c.status == Status::OnTime)
+            .count() as u32;
+        let achievements = achievements.iter().filter(|(_, at)| at.is_some()).count() as u32;
+        Progress {
+            points: on_time + achievements * ACHIEVEMENT_POINTS,
+            best_streak: streaks
+                .tasks
+                .values()
+                .map(|streak| streak.best)
+                .fold(streaks.overall.best, u32::max),
+        }
+    }
+}
+
+pub struct Unlocks {
+    path: PathBuf,
+    unlocked: BTreeSet<String>,
+}
+
+impl Unlocks {
+    const FILE: &str = "unlocked.json";
+
+    pub fn path(data_dir: &std::path::Path) -> PathBuf {
+        data_dir.join(Self::FILE)
+    }
+
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let unlocked = state_file::load(&path)?.unwrap_or_default();
+        Ok(Unlocks { path, unlocked })
+    }
+
+    pub fn is_unlocked(&self, item: Unlockable) -> bool {
+        item.requirement().is_none() || self.unlocked.contains(&item.id())
+    }
+
+    /// Everything that's still locked
+    pub fn locked(&self) -> Vec<Unlockable> {
+        Unlockable::all()
+            .into_iter()
+            .filter(|item| !self.is_unlocked(*item))
+            .collect()
+    }
+
+    /// What the config picked, with anything still locked swapped for
+    /// what everyone starts with. Also says what was locked, if
+    /// anything was
+    pub fn appearance(&self, picked: Appearance) -> (Appearance, Option<String>) {
+        let mut locked = Vec::new();
+        let mut allow = |item: Unlockable| {
+            let unlocked = self.is_unlocked(item);
+            if !unlocked {
+                locked.push(item.name());
+            }
+            unlocked
+        };
+        let character = if allow(Unlockable::Character(picked.character)) {
+            picked.character
+        } else {
+            CharacterChoice::Kitty
+        };
+        let accessory = picked
+            .accessory
+            .filter(|accessory| allow(Unlockable::Accessory(*accessory)));
+        let theme = picked
+            .theme
+            .filter(|theme| allow(Unlockable::Theme(*theme)));
+        let message = (!locked.is_empty()).then(|| {
+            format!(
+                "Not unlocked yet, using the usual ones for now: {}",
+                locked.join(", ")
+            )
+        });
+        (
+            Appearance {
+                character,
+                accessory,
+                theme,
+            },
+            message,
+        )
+    }
+
+    /// Unlock everything that's been earned, returning what wasn't
+    /// unlocked before
+    pub fn check(&mut self, progress: &Progress) -> Vec<Unlockable> {
+        let new: Vec<_> = self
+            .locked()
+            .into_iter()
+            .filter(|item| item.requirement().is_some_and(|r| r.met(progress)))
+            .collect();
+        if !new.is_empty() {
+            self.unlocked.extend(new.iter().map(Unlockable::id));
+            if let Err(e) = self.save() {
+                warn!("Couldn't save to {}: {e}", self.path.display());
+            }
+        }
+        new
+    }
+
+    fn save(&self) -> Result<()> {
+        state_file::save(&self.path, &self.unlocked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlocking_what_was_picked_switches_to_it() {
+        let path =
+            std::env::temp_dir().join(format!("tuigotchi-unlocked-{}.json", std::process::id()));
+        let mut unlocks = Unlocks {
+            path: path.clone(),
+            unlocked: BTreeSet::new(),
+        };
+        let picked = Appearance {
+            character: CharacterChoice::AutismCreature,
+            accessory: None,
+            theme: None,
+        };
+        let (appearance, notice) = unlocks.appearance(picked);
+        assert_eq!(appearance.character, CharacterChoice::Kitty);
+        assert!(notice.is_some());
+
+        let unlocked = unlocks.check(&Progress {
+            points: 0,
+            best_streak: 7,
+        });
+        let _ = std::fs::remove_file(&path);
+        assert!(unlocked.contains(&Unlockable::Character(CharacterChoice::AutismCreature)));
+        assert_eq!(unlocks.appearance(picked), (picked, None));
+    }
+}
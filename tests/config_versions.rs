@@ -0,0 +1,82 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! Configs written for every past version of the config format
+//! should still load. When bumping the config version, copy the new
+//! default config into `tests/golden/config_v<version>.yaml`.
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+fn golden() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn check_config(path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_tuigotchi-health"))
+        .arg("--check-config")
+        .arg(path)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn golden_configs_load() {
+    let mut checked = 0;
+    for entry in std::fs::read_dir(golden()).unwrap() {
+        let path = entry.unwrap().path();
+        let output = check_config(&path);
+        assert!(
+            output.status.success(),
+            "{} didn't load:\n{}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        checked += 1;
+    }
+    assert!(checked > 0, "No golden configs found");
+}
+
+/// Keys added since a config was written shouldn't take keys it
+/// already uses for something else
+#[test]
+fn newer_keys_give_way() {
+    let v3 = std::fs::read_to_string(golden().join("config_v3.yaml")).unwrap();
+    for (key, from, to) in [
+        ("palette", "    Sleep: p", "    Sleep: ':'"),
+        ("palette", "  help: \"?\"", "  help: ':'"),
+        ("early", "  quit: q", "  quit: x"),
+        ("upcoming", "    Sleep: p", "    Sleep: u"),
+        ("stats", "    Sleep: p", "    Sleep: i"),
+        ("amount", "    Sleep: p", "    Sleep: a"),
+    ] {
+        let path =
+            std::env::temp_dir().join(format!("tuigotchi-keys-{}-{key}.yaml", std::process::id()));
+        assert!(v3.contains(from), "config_v3.yaml doesn't have {from}");
+        std::fs::write(&path, v3.replace(from, to)).unwrap();
+        let output = check_config(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(
+            output.status.success(),
+            "{key} took a key that was already used:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
@@ -0,0 +1,227 @@
+/*
+This file is part of Tuigotchi Health.
+
+Tuigotchi Health is free software: you can redistribute it and/or
+modify it under the terms of the GNU General Public License as
+published by the Free Software Foundation, either version 3 of the
+License, or (at your option) any later version.
+
+Tuigotchi Health is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with Tuigotchi Health. If not, see
+<https://www.gnu.org/licenses/>.
+*/
+
+//! The D-Bus service, tested against the real app on its own private
+//! session bus. Needs `dbus-daemon`, and `script` to give the app a
+//! terminal, and is skipped without them.
+
+#![cfg(all(unix, not(target_os = "macos")))]
+
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use zbus::blocking::{connection, Proxy};
+
+const BUS_NAME: &str = "ca.vedapowered.TuigotchiHealth";
+const OBJECT_PATH: &str = "/ca/vedapowered/TuigotchiHealth";
+
+/// What `GetStatus` gives back
+type Status = (String, String, f64, Vec<(String, String, i64)>);
+
+const CONFIG: &str = "\
+dbus_service: true
+notifiers: []
+tasks:
+  - type: Drink
+    schedule: !Interval 4s
+  - type: Eat
+    schedule: !Interval 1h
+  - type: Eyes Rest
+    schedule: !Interval 1h
+    guide:
+      duration: 4s
+      text: Look away
+";
+
+/// Cleans up the bus, the app and its files even if the test fails
+struct Running {
+    dir: PathBuf,
+    bus: Child,
+    app: Option<(Child, ChildStdin)>,
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        if let Some((mut app, mut stdin)) = self.app.take() {
+            // Ask nicely first so it can say goodbye
+            let _ = stdin.write_all(b"q");
+            drop(stdin);
+            thread::sleep(Duration::from_millis(500));
+            let _ = app.kill();
+            let _ = app.wait();
+        }
+        let _ = self.bus.kill();
+        let _ = self.bus.wait();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Keep trying `f` until it gives something, or give up after a while
+fn wait_for<T>(what: &str, mut f: impl FnMut() -> Option<T>) -> T {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(10) {
+        if let Some(t) = f() {
+            return t;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    panic!("Gave up waiting for {what}");
+}
+
+fn task<'a>(status: &'a Status, name: &str) -> &'a (String, String, i64) {
+    status
+        .3
+        .iter()
+        .find(|(task, _, _)| task == name)
+        .unwrap_or_else(|| panic!("{name} isn't in {status:?}"))
+}
+
+fn history(dir: &Path) -> String {
+    std::fs::read_to_string(dir.join("data/tuigotchihealth/history.jsonl")).unwrap_or_default()
+}
+
+#[test]
+fn dbus_service() {
+    let dir = std::env::temp_dir().join(format!("tuigotchi-dbus-{}", std::process::id()));
+    let config_dir = dir.join("config/tuigotchihealth");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(config_dir.join("config.yaml"), CONFIG).unwrap();
+
+    let mut bus = match Command::new("dbus-daemon")
+        .args(["--session", "--nofork", "--print-address"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(bus) => bus,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            eprintln!("No dbus-daemon, skipping");
+            return;
+        }
+        Err(e) => panic!("Couldn't start dbus-daemon: {e}"),
+    };
+    let mut address = String::new();
+    BufReader::new(bus.stdout.take().unwrap())
+        .read_line(&mut address)
+        .unwrap();
+    let address = address.trim().to_string();
+    let mut running = Running {
+        dir: dir.clone(),
+        bus,
+        app: None,
+    };
+
+    let mut app = match Command::new("script")
+        .args(["-qec", env!("CARGO_BIN_EXE_tuigotchi-health"), "/dev/null"])
+        .current_dir(&dir)
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .env("XDG_DATA_HOME", dir.join("data"))
+        .env("DBUS_SESSION_BUS_ADDRESS", &address)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(app) => app,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            eprintln!("No script, skipping");
+            return;
+        }
+        Err(e) => panic!("Couldn't start the app: {e}"),
+    };
+    let stdin = app.stdin.take().unwrap();
+    running.app = Some((app, stdin));
+
+    let conn = connection::Builder::address(address.as_str())
+        .unwrap()
+        .build()
+        .unwrap();
+    let proxy = Proxy::new(&conn, BUS_NAME, OBJECT_PATH, BUS_NAME).unwrap();
+
+    // It's on the bus a moment before it's looked at the tasks
+    let status = wait_for("the service to start", || {
+        let status: Status = proxy.call("GetStatus", &()).ok()?;
+        (!status.3.is_empty()).then_some(status)
+    });
+    assert_eq!(task(&status, "Drink").1, "upcoming");
+    assert_eq!(task(&status, "Eat").1, "upcoming");
+
+    // Drink comes up a few seconds after starting
+    let signals = proxy.receive_signal("TaskDue").unwrap();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for signal in signals {
+            let _ = sender.send(signal.body().deserialize::<(String, i64)>().unwrap());
+        }
+    });
+    let (due, _) = receiver
+        .recv_timeout(Duration::from_secs(10))
+        .expect("No TaskDue signal");
+    assert_eq!(due, "Drink");
+    wait_for("Drink to be due", || {
+        let status: Status = proxy.call("GetStatus", &()).ok()?;
+        (task(&status, "Drink").1 == "current").then_some(())
+    });
+
+    proxy
+        .call::<_, _, ()>("Snooze", &("Drink", 3600u64))
+        .unwrap();
+    let drink = wait_for("Drink to be snoozed", || {
+        let status: Status = proxy.call("GetStatus", &()).ok()?;
+        let drink = task(&status, "Drink").clone();
+        (drink.1 == "upcoming").then_some(drink)
+    });
+    let now = chrono::Local::now().timestamp();
+    assert!(drink.2 > now + 3000, "Drink wasn't snoozed for an hour");
+
+    // Far too long to fit in a date, this used to crash it
+    assert!(proxy
+        .call::<_, _, ()>("Snooze", &("Drink", u64::MAX))
+        .is_err());
+
+    proxy.call::<_, _, ()>("Complete", &("Eat",)).unwrap();
+    wait_for("Eat to be done", || {
+        history(&dir).contains("Eat").then_some(())
+    });
+
+    assert!(proxy.call::<_, _, ()>("Complete", &("Nap",)).is_err());
+
+    // Doing it again while its guide is open doesn't start the
+    // countdown over, it still finishes when the first one would
+    let start = Instant::now();
+    proxy.call::<_, _, ()>("Complete", &("Eyes Rest",)).unwrap();
+    thread::sleep(Duration::from_secs(3));
+    proxy.call::<_, _, ()>("Complete", &("Eyes Rest",)).unwrap();
+    wait_for("Eyes Rest to be done", || {
+        history(&dir).contains("Eyes Rest").then_some(())
+    });
+    assert!(
+        start.elapsed() < Duration::from_secs(6),
+        "The guide started over"
+    );
+
+    // Still going after all that
+    let status: Status = proxy.call("GetStatus", &()).unwrap();
+    assert_eq!(task(&status, "Eat").1, "upcoming");
+}